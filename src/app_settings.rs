@@ -0,0 +1,67 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::commitment_config::CommitmentLevel;
+
+use crate::{
+    clusters::{load_cluster_profiles, save_cluster_profiles},
+    deployer::MaxProgramLen,
+    errors::Error,
+    fees::{UnitLimitMode, UnitPriceMode},
+    files::{settings_path, write_config_file},
+};
+
+// what the gui restores at startup, the keypairs are stored as paths and loaded again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppSettings {
+    pub rpc_url: String,
+    pub websocket_url: String,
+    pub commitment: CommitmentLevel,
+    pub unit_limit: u32,
+    pub unit_price: u64,
+    // files saved before the auto mode existed use a fixed price
+    #[serde(default)]
+    pub unit_price_mode: UnitPriceMode,
+    #[serde(default)]
+    pub unit_limit_mode: UnitLimitMode,
+    #[serde(default)]
+    pub max_program_len: MaxProgramLen,
+    #[serde(default)]
+    pub auto_extend: bool,
+    pub keypair_path: Option<PathBuf>,
+    #[serde(default)]
+    pub fee_payer_path: Option<PathBuf>,
+    // empty when the fee payer gets the refunds
+    #[serde(default)]
+    pub refund_recipient: String,
+    pub program_path: Option<PathBuf>,
+    pub program_keypair_path: Option<PathBuf>,
+    pub solana_config_path: Option<PathBuf>,
+}
+
+// None when nothing was saved yet or the file can't be read
+pub fn load_app_settings() -> Option<AppSettings> {
+    let content = fs::read_to_string(settings_path()).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn save_app_settings(app_settings: &AppSettings) -> Result<(), Error> {
+    let content =
+        serde_json::to_string_pretty(app_settings).map_err(|_| Error::ConfigWriteError)?;
+    write_config_file(&settings_path(), &content)
+}
+
+// forgets the saved settings and the active cluster profile, the profiles themselves are kept
+pub fn reset_app_settings() -> Result<(), Error> {
+    let path = settings_path();
+    if path.exists() {
+        fs::remove_file(path).map_err(|_| Error::ConfigWriteError)?;
+    }
+
+    let mut cluster_profiles = load_cluster_profiles();
+    if cluster_profiles.active.is_some() {
+        cluster_profiles.active = None;
+        save_cluster_profiles(&cluster_profiles)?;
+    }
+    Ok(())
+}
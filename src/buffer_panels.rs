@@ -0,0 +1,142 @@
+use iced::{
+    color,
+    widget::{button, column, row, text},
+    Alignment, Element,
+};
+use lich_deployer::buffers::{BufferState, LocalBuffer, OwnedBuffer};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+
+use crate::{components::copy_to_cliboard_btn, Message};
+
+#[derive(Debug, Clone, Default)]
+pub struct LBuffers {
+    pub local: Vec<LocalBuffer>,
+    pub owned: Vec<OwnedBuffer>,
+}
+
+impl LBuffers {
+    pub fn local_buffers_view(&self) -> Element<Message> {
+        let label = text("Local Buffers: ").size(14).color(color!(0x30cbf2));
+
+        let refresh_btn = button("Refresh").on_press(Message::RefreshLocalBuffers);
+
+        let header = row![label, refresh_btn]
+            .spacing(10)
+            .align_y(Alignment::Center);
+
+        let mut buffers_column = column![header].spacing(5);
+
+        if self.local.is_empty() {
+            buffers_column = buffers_column.push(text("No buffers saved yet").size(14));
+        }
+
+        for buffer in &self.local {
+            let address = buffer.address.to_string();
+            let state = match &buffer.state {
+                BufferState::Missing => String::from("not found (closed or never created)"),
+                BufferState::Open {
+                    lamports,
+                    program_len,
+                    authority,
+                } => format!(
+                    "open, {} bytes, {:.3} SOL, authority: {}",
+                    program_len,
+                    *lamports as f32 / LAMPORTS_PER_SOL as f32,
+                    authority.map_or(String::from("none"), |a| a.to_string())
+                ),
+                BufferState::Invalid => String::from("not a buffer account"),
+            };
+
+            let mut buffer_row = row![
+                text(address.clone()).size(14),
+                copy_to_cliboard_btn(&address),
+                text(state).size(14)
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center);
+
+            if !buffer.keypair_readable {
+                buffer_row = buffer_row.push(
+                    text(format!(
+                        "unreadable keypair file: {}",
+                        buffer.path.display()
+                    ))
+                    .size(14)
+                    .color(color!(0xf75757)),
+                );
+            }
+
+            if let BufferState::Open { .. } = buffer.state {
+                // closing only needs the address, resuming loads the keypair file
+                if buffer.keypair_readable {
+                    let resume_btn = button("Resume")
+                        .on_press(Message::LoadBufferAccount(Ok(buffer.path.clone())));
+                    buffer_row = buffer_row.push(resume_btn);
+                }
+                let close_btn =
+                    button("Close").on_press(Message::CloseBufferTargetInput(address.clone()));
+                buffer_row = buffer_row.push(close_btn);
+            }
+
+            buffers_column = buffers_column.push(buffer_row);
+        }
+
+        buffers_column.into()
+    }
+
+    pub fn owned_buffers_view(&self) -> Element<Message> {
+        let label = text("Buffers owned by the authority: ")
+            .size(14)
+            .color(color!(0x30cbf2));
+
+        let refresh_btn = button("Find Buffers").on_press(Message::RefreshOwnedBuffers);
+
+        let header = row![label, refresh_btn]
+            .spacing(10)
+            .align_y(Alignment::Center);
+
+        let mut buffers_column = column![header].spacing(5);
+
+        if self.owned.is_empty() {
+            buffers_column = buffers_column.push(text("No buffers found").size(14));
+        }
+
+        for buffer in &self.owned {
+            let address = buffer.address.to_string();
+            let details = format!(
+                "{} bytes, {:.3} SOL, {}",
+                buffer.program_len,
+                buffer.lamports as f32 / LAMPORTS_PER_SOL as f32,
+                if buffer.matches_program {
+                    "matches loaded program"
+                } else {
+                    "differs from loaded program"
+                }
+            );
+
+            let select_btn = button("Select").on_press(Message::SelectBuffer(
+                buffer.address,
+                buffer.matches_program,
+            ));
+            let close_btn =
+                button("Close").on_press(Message::CloseBufferTargetInput(address.clone()));
+            let authority_btn = button("Set Authority")
+                .on_press(Message::SelectBufferAuthorityTarget(buffer.address));
+
+            let buffer_row = row![
+                text(address.clone()).size(14),
+                copy_to_cliboard_btn(&address),
+                text(details).size(14),
+                select_btn,
+                close_btn,
+                authority_btn
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center);
+
+            buffers_column = buffers_column.push(buffer_row);
+        }
+
+        buffers_column.into()
+    }
+}
@@ -0,0 +1,161 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    account::Account,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    pubkey::Pubkey,
+    signer::Signer,
+};
+
+use crate::{errors::Error, files::buffers_dir, keypair::load_keypair_from_file};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BufferState {
+    // the account does not exist, it was never created or it was already closed
+    Missing,
+    Open {
+        lamports: u64,
+        program_len: usize,
+        authority: Option<Pubkey>,
+    },
+    // the account exists but it's not an upgradeable loader buffer anymore
+    Invalid,
+}
+
+#[derive(Debug, Clone)]
+pub struct LocalBuffer {
+    pub address: Pubkey,
+    pub path: PathBuf,
+    pub state: BufferState,
+    // false when the keypair file can't be read back, the address comes from the file name
+    pub keypair_readable: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct OwnedBuffer {
+    pub address: Pubkey,
+    pub lamports: u64,
+    pub program_len: usize,
+    // whether the buffer holds exactly the currently loaded program
+    pub matches_program: bool,
+}
+
+// finds every buffer whose authority is the given pubkey, the buffer account layout is
+// [enum tag: u32 = 1][authority option tag: u8 = 1][authority: Pubkey][program data]
+pub async fn load_owned_buffers(
+    rpc_client: Arc<RpcClient>,
+    authority: Pubkey,
+    program_bytes: Vec<u8>,
+) -> Result<Vec<OwnedBuffer>, Error> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, vec![1, 0, 0, 0])),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(4, vec![1])),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(5, authority.to_bytes().to_vec())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+
+    let accounts = rpc_client
+        .get_program_accounts_with_config(&bpf_loader_upgradeable::id(), config)
+        .await
+        .map_err(|e| Error::RpcError(e))?;
+
+    let header_len = UpgradeableLoaderState::size_of_buffer_metadata();
+    let owned_buffers = accounts
+        .into_iter()
+        .map(|(address, account)| {
+            let data = &account.data[header_len.min(account.data.len())..];
+            OwnedBuffer {
+                address,
+                lamports: account.lamports,
+                program_len: data.len(),
+                matches_program: !program_bytes.is_empty() && data == program_bytes.as_slice(),
+            }
+        })
+        .collect();
+    Ok(owned_buffers)
+}
+
+// reads every buffer keypair saved by the app and fetches the state of its account
+pub async fn load_local_buffers(rpc_client: Arc<RpcClient>) -> Result<Vec<LocalBuffer>, Error> {
+    let entries = match fs::read_dir(buffers_dir()) {
+        Ok(entries) => entries,
+        // nothing has been saved yet
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"))
+        .collect();
+    paths.sort();
+
+    // a corrupt keypair file is still listed, its account may hold rent
+    let keypairs: Vec<(PathBuf, Pubkey, bool)> = paths
+        .into_iter()
+        .filter_map(|path| match load_keypair_from_file(path.to_path_buf()) {
+            Ok(keypair) => Some((path, keypair.pubkey(), true)),
+            Err(_) => {
+                let address = path.file_stem()?.to_str()?.parse::<Pubkey>().ok()?;
+                Some((path, address, false))
+            }
+        })
+        .collect();
+    let addresses: Vec<Pubkey> = keypairs.iter().map(|(_, address, _)| *address).collect();
+
+    let mut accounts = Vec::new();
+    // getMultipleAccounts is limited to 100 accounts per request
+    for addresses_batch in addresses.chunks(100) {
+        let batch = rpc_client
+            .get_multiple_accounts(addresses_batch)
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+        accounts.extend(batch);
+    }
+
+    let local_buffers = keypairs
+        .into_iter()
+        .zip(accounts)
+        .map(|((path, address, keypair_readable), account)| LocalBuffer {
+            address,
+            path,
+            state: get_buffer_state(account),
+            keypair_readable,
+        })
+        .collect();
+    Ok(local_buffers)
+}
+
+pub fn get_buffer_state(account: Option<Account>) -> BufferState {
+    let account = match account {
+        Some(account) => account,
+        None => return BufferState::Missing,
+    };
+
+    if account.owner != bpf_loader_upgradeable::id() {
+        return BufferState::Invalid;
+    }
+
+    match bincode::deserialize::<UpgradeableLoaderState>(&account.data) {
+        Ok(UpgradeableLoaderState::Buffer { authority_address }) => BufferState::Open {
+            lamports: account.lamports,
+            program_len: account
+                .data
+                .len()
+                .saturating_sub(UpgradeableLoaderState::size_of_buffer_metadata()),
+            authority: authority_address,
+        },
+        _ => BufferState::Invalid,
+    }
+}
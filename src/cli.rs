@@ -0,0 +1,666 @@
+use std::{collections::HashMap, path::PathBuf, str::FromStr, sync::Arc};
+
+use lich_deployer::{
+    clusters::RPC_URL,
+    deployer::{
+        buffer_lamports, get_program_bytes, save_program_bytes, BufferTarget, Deployer,
+        MaxProgramLen, ProgramTarget, Progress, UpgradeAuthorityChange,
+    },
+    errors::Error,
+    fees::{UnitLimitMode, UnitPriceMode},
+    files::default_keypair_path,
+    instructions::{PriorityFees, DEFAULT_UNIT_LIMIT, DEFAULT_UNIT_PRICE},
+    keypair::new_buffer_keypair,
+    multisig::{squads_vault_address, DEFAULT_VAULT_INDEX},
+    offline::{parse_presigner, OfflineAction, OfflineTransaction},
+    solana_config::{default_config_path, load_solana_config},
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signature},
+    signer::Signer,
+};
+use tokio::runtime::Runtime;
+
+pub const COMMANDS: [&str; 17] = [
+    "estimate",
+    "write-buffer",
+    "deploy",
+    "upgrade",
+    "verify",
+    "inspect",
+    "dump",
+    "set-buffer-authority",
+    "set-upgrade-authority",
+    "set-immutable",
+    "upgrade-proposal",
+    "offline-message",
+    "offline-submit",
+    "nonce-create",
+    "nonce-show",
+    "nonce-advance",
+    "close",
+];
+
+const USAGE: &str = "Usage: lich-deployer <COMMAND> [OPTIONS]
+
+Runs without the GUI when a command is given.
+
+Commands:
+  estimate               Print the cost of writing --program and deploying it as JSON
+  write-buffer           Create a buffer (or resume one with --buffer) and write the program to it
+  deploy                 Deploy the program from a written buffer
+  upgrade                Upgrade an existing program from a written buffer
+  verify                 Check that the deployed program matches --program, exits with 7 when it doesn't
+  inspect                Print the upgrade authority, last deployed slot, sizes, rent and SHA-256 of a program as JSON
+  dump                   Save the executable of a program (or of --buffer) to --output
+  set-buffer-authority   Transfer a buffer authority to --new-authority
+  set-upgrade-authority  Transfer a program upgrade authority to --new-authority or --new-authority-keypair
+  set-immutable          Revoke a program upgrade authority, requires --confirm with the program id
+  upgrade-proposal       Print the unsigned upgrade message for a multisig vault that owns --buffer
+  offline-message        Print the --action message for an --offline-authority, built on a --nonce
+  offline-submit         Submit an offline --message with the pasted --signatures
+  nonce-create           Create a durable nonce account with the authority as its nonce authority
+  nonce-show             Print the current nonce and authority of --nonce
+  nonce-advance          Advance --nonce, anything signed on the previous nonce becomes invalid
+  close                  Close a buffer and send its lamports to --recipient
+
+Options:
+  --config <PATH>                 Solana cli config file (defaults to ~/.config/solana/cli/config.yml)
+  --url <URL>                     RPC URL (defaults to the config json_rpc_url)
+  --keypair <PATH>                Authority keypair (defaults to the config keypair_path)
+  --fee-payer <PATH>              Keypair that pays the fees and rent (defaults to --keypair)
+  --program <PATH>                Program .so file
+  --program-keypair <PATH>        Program account keypair (estimate: priced as an upgrade when deployed)
+  --program-id <PUBKEY>           Program to upgrade (or change the authority of), instead of --program-keypair
+  --buffer <PATH|ADDRESS>         Buffer keypair file or address
+  --new-authority <PUBKEY>        New buffer or upgrade authority
+  --new-authority-keypair <PATH>  New upgrade authority keypair, it signs the checked transfer
+  --confirm <PUBKEY>              Program id typed back to confirm set-immutable, it can't be undone
+  --multisig <PUBKEY>             Squads multisig, its vault is derived instead of passing --vault
+  --vault-index <INDEX>           Squads vault index used with --multisig (defaults to 0)
+  --vault <PUBKEY>                Multisig vault, the upgrade and buffer authority of the proposal
+  --spill <PUBKEY>                Receives the buffer lamports after the upgrade (defaults to the fee payer, the vault for upgrade-proposal)
+  --offline-authority <PUBKEY>    Authority that signs offline, instead of --keypair
+  --nonce <PUBKEY>                Durable nonce account for deploy, upgrade and the offline message
+  --action <ACTION>               Offline action: deploy, upgrade, set-upgrade-authority or set-immutable
+  --message <BASE64|BASE58>       Offline message printed by offline-message
+  --signatures <PUBKEY=SIG,...>   Signatures of the offline signers, comma separated
+  --output <PATH>                 File the dumped executable is written to
+  --recipient <PUBKEY>            Recipient of the closed buffer lamports (defaults to --spill)
+  --auto-extend                   Extend the programdata account when the upgrade doesn't fit
+  --max-len <BYTES|Nx>            Programdata size reserved on deploy, like 2x (defaults to the program size)
+  --unit-limit <UNITS>            Compute unit limit for every transaction, disables the simulation
+  --unit-limit-margin <PERCENT>   Margin over the simulated compute units (defaults to 20)
+  --unit-price <MICRO_LAMPORTS>   Compute unit price (the maximum with --fee-percentile)
+  --fee-percentile <PERCENTILE>   Price the writes from this percentile of the recent fees
+  -h, --help                      Print this help";
+
+// options that don't take a value
+const FLAGS: [&str; 1] = ["auto-extend"];
+
+struct CliArgs {
+    command: String,
+    options: HashMap<String, String>,
+}
+
+impl CliArgs {
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn required(&self, name: &str) -> Result<&str, Error> {
+        self.option(name)
+            .ok_or(Error::InvalidCliArguments(format!("missing --{}", name)))
+    }
+}
+
+// parses the args, runs the command and returns the process exit code
+pub fn run(args: Vec<String>) -> i32 {
+    if args
+        .iter()
+        .any(|arg| arg == "-h" || arg == "--help" || arg == "help")
+    {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let cli_args = match parse_args(args) {
+        Ok(cli_args) => cli_args,
+        Err(e) => {
+            eprintln!("Error: {:?}\n\n{}", e, USAGE);
+            return e.exit_code();
+        }
+    };
+
+    let runtime = Runtime::new().expect("Cannot start the tokio runtime");
+    match runtime.block_on(run_command(cli_args)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            e.exit_code()
+        }
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<CliArgs, Error> {
+    let mut args = args.into_iter();
+    let command = args
+        .next()
+        .filter(|command| COMMANDS.contains(&command.as_str()))
+        .ok_or(Error::InvalidCliArguments(String::from("unknown command")))?;
+
+    let mut options = HashMap::new();
+    while let Some(arg) = args.next() {
+        let name = arg
+            .strip_prefix("--")
+            .ok_or(Error::InvalidCliArguments(format!(
+                "unexpected argument {}",
+                arg
+            )))?;
+        if FLAGS.contains(&name) {
+            options.insert(name.to_string(), String::from("true"));
+            continue;
+        }
+        let value = args.next().ok_or(Error::InvalidCliArguments(format!(
+            "missing value for {}",
+            arg
+        )))?;
+        options.insert(name.to_string(), value);
+    }
+    Ok(CliArgs { command, options })
+}
+
+async fn run_command(cli_args: CliArgs) -> Result<(), Error> {
+    let deployer = load_deployer(&cli_args).await?;
+
+    match cli_args.command.as_str() {
+        "estimate" => {
+            let program_bytes = get_program_bytes(cli_args.required("program")?)?;
+            let buffer = match cli_args.option("buffer") {
+                Some(buffer) => BufferTarget::Resume(parse_buffer(buffer)?),
+                None => BufferTarget::New(Arc::new(Keypair::new())),
+            };
+            let program_address = match parse_program_target(&cli_args) {
+                Ok(program) => Some(program.pubkey()),
+                Err(Error::ProgramAccountNotLoaded) => None,
+                Err(e) => return Err(e),
+            };
+            let cost_estimate = deployer
+                .estimate_cost(&buffer, program_address.as_ref(), &program_bytes)
+                .await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&cost_estimate).map_err(|_| Error::UnexpectedError)?
+            );
+            Ok(())
+        }
+        "write-buffer" => {
+            let program_bytes = get_program_bytes(cli_args.required("program")?)?;
+            let buffer = match cli_args.option("buffer") {
+                Some(buffer) => BufferTarget::Resume(parse_buffer(buffer)?),
+                None => {
+                    let (buffer_account, seed_phrase) = new_buffer_keypair();
+                    println!(
+                        "To recover the buffer keypair use `solana-keygen recover` with the seed phrase: {}",
+                        seed_phrase
+                    );
+                    BufferTarget::New(Arc::new(buffer_account))
+                }
+            };
+            println!("Buffer: {}", buffer.pubkey());
+            deployer
+                .write_buffer(buffer, &program_bytes, |progress| print_progress(&progress))
+                .await?;
+            Ok(())
+        }
+        "deploy" | "upgrade" => {
+            let program_bytes = get_program_bytes(cli_args.required("program")?)?;
+            let program = parse_program_target(&cli_args)?;
+            let buffer_address = parse_buffer(cli_args.required("buffer")?)?;
+
+            let is_deployed = deployer.is_program_deployed(&program.pubkey()).await;
+            match (cli_args.command.as_str(), is_deployed) {
+                ("deploy", true) => return Err(Error::ProgramAlreadyDeployed),
+                ("upgrade", false) => return Err(Error::ProgramNotDeployed),
+                _ => {}
+            }
+
+            let signature = deployer
+                .deploy_or_upgrade(&program, &buffer_address, &program_bytes)
+                .await?;
+            print_signature(signature);
+            verify_program(&deployer, &program.pubkey(), &program_bytes).await
+        }
+        "verify" => {
+            let program_bytes = get_program_bytes(cli_args.required("program")?)?;
+            let program = parse_program_target(&cli_args)?;
+            verify_program(&deployer, &program.pubkey(), &program_bytes).await
+        }
+        "inspect" => {
+            let program = parse_program_target(&cli_args)?;
+            let program_info = deployer.inspect_program(&program.pubkey()).await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&program_info).map_err(|_| Error::UnexpectedError)?
+            );
+            Ok(())
+        }
+        "dump" => {
+            let address = match cli_args.option("buffer") {
+                Some(buffer) => parse_buffer(buffer)?,
+                None => parse_program_target(&cli_args)?.pubkey(),
+            };
+            let program_bytes = deployer.fetch_executable(&address).await?;
+            let output = PathBuf::from(cli_args.required("output")?);
+            save_program_bytes(&output, &program_bytes)?;
+            println!(
+                "{} bytes from {} saved to {}",
+                program_bytes.len(),
+                address,
+                output.display()
+            );
+            Ok(())
+        }
+        "set-buffer-authority" => {
+            let buffer_address = parse_buffer(cli_args.required("buffer")?)?;
+            let new_authority = Pubkey::from_str(cli_args.required("new-authority")?)
+                .map_err(|_| Error::UndefinedNewBufferAuthority)?;
+            let signature = deployer
+                .set_buffer_authority(&buffer_address, &new_authority)
+                .await?;
+            print_signature(signature);
+            Ok(())
+        }
+        "set-upgrade-authority" => {
+            let program_address = parse_program_target(&cli_args)?.pubkey();
+            let change = match (
+                cli_args.option("new-authority-keypair"),
+                cli_args.option("new-authority"),
+            ) {
+                (Some(path), _) => {
+                    let new_authority =
+                        read_keypair_file(path).map_err(|_| Error::InvalidKeypairFile)?;
+                    UpgradeAuthorityChange::TransferChecked(Arc::new(new_authority))
+                }
+                (None, Some(new_authority)) => UpgradeAuthorityChange::Transfer(
+                    Pubkey::from_str(new_authority)
+                        .map_err(|_| Error::UndefinedNewUpgradeAuthority)?,
+                ),
+                (None, None) => return Err(Error::UndefinedNewUpgradeAuthority),
+            };
+            let signature = deployer
+                .set_upgrade_authority(&program_address, &change)
+                .await?;
+            print_signature(signature);
+            Ok(())
+        }
+        "set-immutable" => {
+            let program_address = parse_program_target(&cli_args)?.pubkey();
+            // same typed confirmation as the gui, there's no way back from this
+            if cli_args.option("confirm") != Some(program_address.to_string().as_str()) {
+                return Err(Error::ImmutableNotConfirmed);
+            }
+            let signature = deployer
+                .set_upgrade_authority(&program_address, &UpgradeAuthorityChange::Revoke)
+                .await?;
+            print_signature(signature);
+            Ok(())
+        }
+        "upgrade-proposal" => {
+            let program_address = parse_program_target(&cli_args)?.pubkey();
+            let buffer_address = parse_buffer(cli_args.required("buffer")?)?;
+            let vault_address = match (cli_args.option("vault"), cli_args.option("multisig")) {
+                (Some(vault), _) => {
+                    Pubkey::from_str(vault).map_err(|_| Error::InvalidVaultAddress)?
+                }
+                (None, Some(multisig)) => {
+                    let multisig_address =
+                        Pubkey::from_str(multisig).map_err(|_| Error::InvalidMultisigAddress)?;
+                    let vault_index = match cli_args.option("vault-index") {
+                        Some(vault_index) => vault_index.parse().map_err(|_| {
+                            Error::InvalidCliArguments(String::from("invalid --vault-index"))
+                        })?,
+                        None => DEFAULT_VAULT_INDEX,
+                    };
+                    squads_vault_address(&multisig_address, vault_index)
+                }
+                (None, None) => return Err(Error::InvalidVaultAddress),
+            };
+            let spill_address = match cli_args.option("spill") {
+                Some(spill) => Pubkey::from_str(spill).map_err(|_| Error::InvalidSpillAddress)?,
+                None => vault_address,
+            };
+            let upgrade_proposal = deployer
+                .upgrade_proposal(
+                    &program_address,
+                    &buffer_address,
+                    &vault_address,
+                    &spill_address,
+                )
+                .await?;
+            println!("Vault: {}", upgrade_proposal.vault_address);
+            println!("Spill: {}", upgrade_proposal.spill_address);
+            println!("Message (base58): {}", upgrade_proposal.message.base58);
+            println!("Message (base64): {}", upgrade_proposal.message.base64);
+            Ok(())
+        }
+        "offline-message" => {
+            let offline_authority = Pubkey::from_str(cli_args.required("offline-authority")?)
+                .map_err(|_| Error::InvalidOfflineAuthority)?;
+            let nonce_address = parse_nonce(cli_args.required("nonce")?)?;
+            let program = parse_program_target(&cli_args)?;
+            let action = match cli_args.required("action")? {
+                "deploy" => match program {
+                    ProgramTarget::Keypair(program_keypair) => OfflineAction::Deploy {
+                        program_keypair,
+                        buffer_address: parse_buffer(cli_args.required("buffer")?)?,
+                    },
+                    ProgramTarget::Address(_) => return Err(Error::ProgramAccountNotLoaded),
+                },
+                "upgrade" => OfflineAction::Upgrade {
+                    program_address: program.pubkey(),
+                    buffer_address: parse_buffer(cli_args.required("buffer")?)?,
+                },
+                "set-upgrade-authority" => OfflineAction::SetUpgradeAuthority {
+                    program_address: program.pubkey(),
+                    new_authority: Some(
+                        Pubkey::from_str(cli_args.required("new-authority")?)
+                            .map_err(|_| Error::UndefinedNewUpgradeAuthority)?,
+                    ),
+                },
+                "set-immutable" => {
+                    if cli_args.option("confirm") != Some(program.pubkey().to_string().as_str()) {
+                        return Err(Error::ImmutableNotConfirmed);
+                    }
+                    OfflineAction::SetUpgradeAuthority {
+                        program_address: program.pubkey(),
+                        new_authority: None,
+                    }
+                }
+                _ => return Err(Error::InvalidCliArguments(String::from("invalid --action"))),
+            };
+            let offline_transaction = deployer
+                .offline_transaction(&action, &offline_authority, &nonce_address)
+                .await?;
+            let message = offline_transaction.message();
+            for signer in offline_transaction.missing_signers() {
+                println!("Signer: {}", signer);
+            }
+            println!("Message (base58): {}", message.base58);
+            println!("Message (base64): {}", message.base64);
+            Ok(())
+        }
+        "offline-submit" => {
+            // the local keypairs sign again, their signatures are deterministic
+            let program_keypair = match parse_program_target(&cli_args) {
+                Ok(ProgramTarget::Keypair(program_keypair)) => Some(program_keypair),
+                _ => None,
+            };
+            let mut local_signers = vec![deployer.payer.as_ref(), deployer.authority.as_ref()];
+            if let Some(program_keypair) = &program_keypair {
+                local_signers.push(program_keypair.as_ref());
+            }
+            let mut offline_transaction =
+                OfflineTransaction::from_encoded(cli_args.required("message")?, &local_signers)?;
+            for presigner in cli_args.required("signatures")?.split(',') {
+                let (signer, signature) = parse_presigner(presigner)?;
+                offline_transaction.add_signature(&signer, &signature)?;
+            }
+            let signature = deployer
+                .send_offline_transaction(&offline_transaction)
+                .await?;
+            print_signature(signature);
+            Ok(())
+        }
+        "nonce-create" => {
+            let nonce_account = Keypair::new();
+            let signature = deployer.create_nonce_account(&nonce_account).await?;
+            println!("Nonce account: {}", nonce_account.pubkey());
+            print_signature(signature);
+            Ok(())
+        }
+        "nonce-show" => {
+            let nonce_info = deployer
+                .nonce_info(&parse_nonce(cli_args.required("nonce")?)?)
+                .await?;
+            println!("Nonce: {}", nonce_info.blockhash);
+            println!("Authority: {}", nonce_info.authority);
+            Ok(())
+        }
+        "nonce-advance" => {
+            let signature = deployer
+                .advance_nonce(&parse_nonce(cli_args.required("nonce")?)?)
+                .await?;
+            print_signature(signature);
+            Ok(())
+        }
+        "close" => {
+            let buffer_address = parse_buffer(cli_args.required("buffer")?)?;
+            let recipient = match cli_args.option("recipient") {
+                Some(recipient) => {
+                    Pubkey::from_str(recipient).map_err(|_| Error::InvalidRecipient)?
+                }
+                None => deployer.spill_address(),
+            };
+            let lamports = buffer_lamports(deployer.rpc_client.clone(), buffer_address).await?;
+            println!(
+                "Recovering {:.6} SOL from {} to {}",
+                lamports as f64 / LAMPORTS_PER_SOL as f64,
+                buffer_address,
+                recipient
+            );
+            let signature = deployer.close_buffer(&buffer_address, &recipient).await?;
+            print_signature(signature);
+            Ok(())
+        }
+        _ => Err(Error::InvalidCliArguments(String::from("unknown command"))),
+    }
+}
+
+async fn load_deployer(cli_args: &CliArgs) -> Result<Deployer, Error> {
+    // an explicit config must load, the default one is only used when it exists
+    let solana_config = match cli_args.option("config") {
+        Some(config_path) => Some(load_solana_config(&PathBuf::from(config_path))?),
+        None => default_config_path().and_then(|path| load_solana_config(&path).ok()),
+    };
+
+    let rpc_url = cli_args.option("url").map(String::from).unwrap_or(
+        solana_config
+            .as_ref()
+            .map_or(RPC_URL.to_string(), |config| config.rpc_url.clone()),
+    );
+    let commitment = solana_config
+        .as_ref()
+        .map_or(CommitmentConfig::confirmed(), |config| config.commitment);
+    let rpc_client = Arc::new(RpcClient::new_with_commitment(rpc_url, commitment));
+
+    // unlike the gui, a missing keypair file is an error instead of a random keypair
+    let keypair_path = cli_args.option("keypair").map(PathBuf::from).unwrap_or(
+        solana_config
+            .as_ref()
+            .map_or(default_keypair_path(), |config| config.keypair_path.clone()),
+    );
+    let authority =
+        Arc::new(read_keypair_file(&keypair_path).map_err(|_| Error::InvalidKeypairFile)?);
+    let fee_payer = match cli_args.option("fee-payer") {
+        Some(path) => Arc::new(read_keypair_file(path).map_err(|_| Error::InvalidKeypairFile)?),
+        None => authority.clone(),
+    };
+
+    let unit_limit = match cli_args.option("unit-limit") {
+        Some(unit_limit) => unit_limit
+            .parse()
+            .map_err(|_| Error::InvalidCliArguments(String::from("invalid --unit-limit")))?,
+        None => DEFAULT_UNIT_LIMIT,
+    };
+
+    let unit_price = match cli_args.option("unit-price") {
+        Some(unit_price) => unit_price
+            .parse()
+            .map_err(|_| Error::InvalidCliArguments(String::from("invalid --unit-price")))?,
+        None => DEFAULT_UNIT_PRICE,
+    };
+
+    // an explicit limit is used as is, otherwise every transaction type is simulated
+    let unit_limit_mode = match (
+        cli_args.option("unit-limit-margin"),
+        cli_args.option("unit-limit"),
+    ) {
+        (Some(margin_percent), _) => UnitLimitMode::Simulated {
+            margin_percent: margin_percent.parse().map_err(|_| {
+                Error::InvalidCliArguments(String::from("invalid --unit-limit-margin"))
+            })?,
+        },
+        (None, Some(_)) => UnitLimitMode::Fixed,
+        (None, None) => UnitLimitMode::default(),
+    };
+
+    let max_program_len = match cli_args.option("max-len") {
+        Some(max_program_len) => max_program_len
+            .parse()
+            .map_err(|_| Error::InvalidCliArguments(String::from("invalid --max-len")))?,
+        None => MaxProgramLen::Exact,
+    };
+
+    let unit_price_mode = match cli_args.option("fee-percentile") {
+        Some(percentile) => UnitPriceMode::Auto {
+            percentile: percentile
+                .parse::<u8>()
+                .ok()
+                .filter(|percentile| *percentile <= 100)
+                .ok_or(Error::InvalidCliArguments(String::from(
+                    "invalid --fee-percentile",
+                )))?,
+        },
+        None => UnitPriceMode::Fixed,
+    };
+
+    let nonce_account = match cli_args.option("nonce") {
+        Some(nonce) => Some(parse_nonce(nonce)?),
+        None => None,
+    };
+
+    let spill_address = match cli_args.option("spill") {
+        Some(spill) => Some(Pubkey::from_str(spill).map_err(|_| Error::InvalidSpillAddress)?),
+        None => None,
+    };
+
+    let balance = rpc_client
+        .get_balance(&authority.pubkey())
+        .await
+        .map_err(|_| Error::FetchBalanceError)?;
+
+    // stderr keeps stdout clean for the estimate json
+    eprintln!("RPC URL: {}", rpc_client.url());
+    eprintln!(
+        "Authority: {} ({:.3} SOL)",
+        authority.pubkey(),
+        balance as f64 / LAMPORTS_PER_SOL as f64
+    );
+    if fee_payer.pubkey() != authority.pubkey() {
+        let fee_payer_balance = rpc_client
+            .get_balance(&fee_payer.pubkey())
+            .await
+            .map_err(|_| Error::FetchBalanceError)?;
+        eprintln!(
+            "Fee payer: {} ({:.3} SOL)",
+            fee_payer.pubkey(),
+            fee_payer_balance as f64 / LAMPORTS_PER_SOL as f64
+        );
+    }
+
+    Ok(Deployer::new(
+        rpc_client,
+        authority,
+        PriorityFees {
+            unit_limit,
+            unit_price,
+        },
+    )
+    .with_unit_price_mode(unit_price_mode)
+    .with_unit_limit_mode(unit_limit_mode)
+    .with_max_program_len(max_program_len)
+    .with_auto_extend(cli_args.option("auto-extend").is_some())
+    .with_nonce_account(nonce_account)
+    .with_fee_payer(fee_payer)
+    .with_spill_address(spill_address))
+}
+
+// upgrades only need the program id, deploys need the keypair
+fn parse_program_target(cli_args: &CliArgs) -> Result<ProgramTarget, Error> {
+    match (
+        cli_args.option("program-keypair"),
+        cli_args.option("program-id"),
+    ) {
+        (Some(path), _) => {
+            let program_keypair = read_keypair_file(path).map_err(|_| Error::InvalidKeypairFile)?;
+            Ok(ProgramTarget::Keypair(Arc::new(program_keypair)))
+        }
+        (None, Some(program_id)) => Pubkey::from_str(program_id)
+            .map(ProgramTarget::Address)
+            .map_err(|_| Error::InvalidProgramAccount),
+        (None, None) => Err(Error::ProgramAccountNotLoaded),
+    }
+}
+
+// the buffer can be either a keypair file or a plain address
+fn parse_buffer(buffer: &str) -> Result<Pubkey, Error> {
+    if PathBuf::from(buffer).is_file() {
+        let keypair = read_keypair_file(buffer).map_err(|_| Error::InvalidKeypairFile)?;
+        Ok(keypair.pubkey())
+    } else {
+        Pubkey::from_str(buffer).map_err(|_| Error::InvalidBufferAccount)
+    }
+}
+
+fn parse_nonce(nonce: &str) -> Result<Pubkey, Error> {
+    Pubkey::from_str(nonce).map_err(|_| Error::InvalidNonceAccount)
+}
+
+fn print_progress(progress: &Progress) {
+    match progress {
+        Progress::Idle => println!("Starting"),
+        Progress::BufferKeypairSaved { path } => {
+            println!("Buffer keypair saved to {}", path.display())
+        }
+        Progress::Sending { sent, total } => {
+            println!("Sending write transactions {}/{}", sent, total)
+        }
+        Progress::Retrying { unit_price } => {
+            println!("Retrying with a unit price of {}", unit_price)
+        }
+        Progress::Verifying => println!("Verifying the buffer contents"),
+        Progress::Rewriting { ranges } => {
+            println!("Writing {} mismatched ranges again", ranges.len());
+            for range in ranges {
+                println!("  bytes {}..{}", range.start, range.end);
+            }
+        }
+        Progress::Completed { buffer_address } => {
+            println!("Data written and verified on {}", buffer_address)
+        }
+    }
+}
+
+async fn verify_program(
+    deployer: &Deployer,
+    program_address: &Pubkey,
+    program_bytes: &[u8],
+) -> Result<(), Error> {
+    let verification = deployer
+        .verify_program(program_address, program_bytes)
+        .await?;
+    println!("Program {}: {}", program_address, verification);
+    match verification.is_verified() {
+        true => Ok(()),
+        false => Err(Error::ProgramMismatch),
+    }
+}
+
+fn print_signature(signature: Signature) {
+    println!("Signature: {}", signature);
+}
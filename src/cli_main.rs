@@ -0,0 +1,9 @@
+use std::{env, process};
+
+mod cli;
+
+// same commands as `lich-deployer <COMMAND>`, without iced or a file dialog backend
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    process::exit(cli::run(args));
+}
@@ -0,0 +1,136 @@
+use std::{fmt, fs, path::PathBuf, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+
+use crate::{
+    errors::Error,
+    fees::{UnitLimitMode, UnitPriceMode},
+    files::{clusters_path, write_config_file},
+    instructions::{DEFAULT_UNIT_LIMIT, DEFAULT_UNIT_PRICE},
+};
+
+// used until a solana cli config or a cluster profile says otherwise
+pub const RPC_URL: &str = "https://api.devnet.solana.com";
+
+// everything that changes when switching clusters, the keypairs are optional so a profile
+// without them keeps the ones already loaded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClusterProfile {
+    pub name: String,
+    pub rpc_url: String,
+    pub websocket_url: String,
+    pub unit_limit: u32,
+    pub unit_price: u64,
+    #[serde(default)]
+    pub unit_price_mode: UnitPriceMode,
+    #[serde(default)]
+    pub unit_limit_mode: UnitLimitMode,
+    pub keypair_path: Option<PathBuf>,
+    pub program_keypair_path: Option<PathBuf>,
+}
+
+impl ClusterProfile {
+    fn preset(name: &str, rpc_url: &str, websocket_url: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rpc_url: rpc_url.to_string(),
+            websocket_url: websocket_url.to_string(),
+            unit_limit: DEFAULT_UNIT_LIMIT,
+            unit_price: DEFAULT_UNIT_PRICE,
+            unit_price_mode: UnitPriceMode::Fixed,
+            unit_limit_mode: UnitLimitMode::default(),
+            keypair_path: None,
+            program_keypair_path: None,
+        }
+    }
+
+    pub fn is_mainnet(&self) -> bool {
+        is_mainnet_url(&self.rpc_url)
+    }
+}
+
+// the pick list shows the profile name
+impl fmt::Display for ClusterProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClusterProfiles {
+    pub active: Option<String>,
+    pub profiles: Vec<ClusterProfile>,
+}
+
+impl Default for ClusterProfiles {
+    fn default() -> Self {
+        Self {
+            active: None,
+            profiles: vec![
+                ClusterProfile::preset(
+                    "devnet",
+                    "https://api.devnet.solana.com",
+                    "wss://api.devnet.solana.com/",
+                ),
+                ClusterProfile::preset(
+                    "testnet",
+                    "https://api.testnet.solana.com",
+                    "wss://api.testnet.solana.com/",
+                ),
+                ClusterProfile::preset(
+                    "mainnet-beta",
+                    "https://api.mainnet-beta.solana.com",
+                    "wss://api.mainnet-beta.solana.com/",
+                ),
+                ClusterProfile::preset("localnet", "http://localhost:8899", "ws://localhost:8900"),
+            ],
+        }
+    }
+}
+
+impl ClusterProfiles {
+    pub fn active_profile(&self) -> Option<&ClusterProfile> {
+        let active = self.active.as_deref()?;
+        self.profiles.iter().find(|profile| profile.name == active)
+    }
+
+    // replaces the profile with the same name or adds it, and makes it the active one
+    pub fn upsert(&mut self, profile: ClusterProfile) {
+        self.active = Some(profile.name.clone());
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+}
+
+pub const MAINNET_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+
+// rpc providers usually keep the cluster name in the url, same for the public endpoint
+pub fn is_mainnet_url(rpc_url: &str) -> bool {
+    rpc_url.contains("mainnet")
+}
+
+// private rpcs on their own domains are caught by the genesis hash, the url is only used
+// when the rpc can't be reached
+pub async fn is_mainnet(rpc_client: Arc<RpcClient>) -> bool {
+    match rpc_client.get_genesis_hash().await {
+        Ok(genesis_hash) => genesis_hash.to_string() == MAINNET_GENESIS_HASH,
+        Err(_) => is_mainnet_url(&rpc_client.url()),
+    }
+}
+
+// missing or unreadable files fall back to the presets
+pub fn load_cluster_profiles() -> ClusterProfiles {
+    fs::read_to_string(clusters_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_cluster_profiles(cluster_profiles: &ClusterProfiles) -> Result<(), Error> {
+    let content =
+        serde_json::to_string_pretty(cluster_profiles).map_err(|_| Error::ConfigWriteError)?;
+    write_config_file(&clusters_path(), &content)
+}
@@ -1,9 +1,10 @@
-use crate::{errors::Error, Message};
+use crate::Message;
 use iced::{
     color,
     widget::{button, text},
     Element,
 };
+use lich_deployer::errors::Error;
 
 pub fn copy_to_cliboard_btn(value: &str) -> Element<'static, Message> {
     if value == "" {
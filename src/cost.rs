@@ -0,0 +1,218 @@
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{bpf_loader_upgradeable::UpgradeableLoaderState, pubkey::Pubkey};
+
+use crate::{
+    deployer::{ProgramExtension, PROGRAM_EXTRA_SPACE},
+    errors::Error,
+    instructions::PriorityFees,
+};
+
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+// what a run is going to cost, in lamports, serialized as is for scripts
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostEstimate {
+    pub is_upgrade: bool,
+    pub program_len: usize,
+    pub max_program_len: usize,
+    pub buffer_rent: u64,
+    pub programdata_rent: u64,
+    // part of the programdata rent paid for the room above the current binary
+    pub extra_programdata_rent: u64,
+    // an upgrade whose binary doesn't fit extends the programdata account first
+    pub extension: Option<ProgramExtension>,
+    pub program_rent: u64,
+    pub transactions: usize,
+    pub write_transactions: usize,
+    pub signature_fees: u64,
+    // an upper bound when the unit price is estimated, the configured price is the maximum
+    pub priority_fees: u64,
+    // the buffer lamports go back to the payer (or the spill account) once it's consumed
+    pub buffer_refund: u64,
+    pub refund_recipient: String,
+    pub total: u64,
+    // the refund only lowers the cost when it goes back to the payer
+    pub net_total: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostParams {
+    pub program_len: usize,
+    pub max_program_len: usize,
+    pub extension: Option<ProgramExtension>,
+    pub is_upgrade: bool,
+    pub creates_buffer: bool,
+    pub write_transactions: usize,
+    pub priority_fees: PriorityFees,
+    pub fee_payer: Pubkey,
+    pub authority: Pubkey,
+    // the spill account on upgrades, a deploy always refunds the payer
+    pub refund_recipient: Pubkey,
+}
+
+pub async fn estimate_cost(
+    rpc_client: &RpcClient,
+    params: CostParams,
+) -> Result<CostEstimate, Error> {
+    let rent = |data_len: usize| async move {
+        rpc_client
+            .get_minimum_balance_for_rent_exemption(data_len)
+            .await
+            .map_err(|e| Error::RpcError(e))
+    };
+
+    // same sizes the deployer funds
+    let buffer_rent = match params.creates_buffer {
+        true => rent(params.program_len + PROGRAM_EXTRA_SPACE).await?,
+        false => 0,
+    };
+    let (programdata_rent, extra_programdata_rent, program_rent) = match params.is_upgrade {
+        true => (0, 0, 0),
+        false => {
+            let programdata_rent = rent(UpgradeableLoaderState::size_of_programdata(
+                params.max_program_len,
+            ))
+            .await?;
+            let exact_programdata_rent = rent(UpgradeableLoaderState::size_of_programdata(
+                params.program_len,
+            ))
+            .await?;
+            (
+                programdata_rent,
+                programdata_rent.saturating_sub(exact_programdata_rent),
+                rent(UpgradeableLoaderState::size_of_program()).await?,
+            )
+        }
+    };
+    let buffer_refund = rent(params.program_len + PROGRAM_EXTRA_SPACE).await?;
+
+    let signatures = signature_count(&params);
+    let transactions = transaction_count(&params);
+    let extension_rent = params.extension.map_or(0, |extension| extension.rent);
+
+    let signature_fees = signatures * LAMPORTS_PER_SIGNATURE;
+    let priority_fees = transactions as u64 * priority_fee_per_tx(&params.priority_fees);
+
+    let total = buffer_rent
+        + programdata_rent
+        + program_rent
+        + extension_rent
+        + signature_fees
+        + priority_fees;
+    Ok(CostEstimate {
+        is_upgrade: params.is_upgrade,
+        program_len: params.program_len,
+        max_program_len: params.max_program_len,
+        buffer_rent,
+        programdata_rent,
+        extra_programdata_rent,
+        extension: params.extension,
+        program_rent,
+        transactions,
+        write_transactions: params.write_transactions,
+        signature_fees,
+        priority_fees,
+        buffer_refund,
+        refund_recipient: params.refund_recipient.to_string(),
+        total,
+        net_total: match params.refund_recipient == params.fee_payer {
+            true => total.saturating_sub(buffer_refund),
+            false => total,
+        },
+    })
+}
+
+// create buffer (payer + buffer), every write (payer + authority), the extension if any
+// (payer), then deploy (payer + authority + program) or upgrade (payer + authority), the
+// authority signature is only an extra one when it doesn't pay
+pub fn signature_count(params: &CostParams) -> u64 {
+    let authority_signatures = (params.fee_payer != params.authority) as u64;
+    let create_signatures = if params.creates_buffer { 2 } else { 0 };
+    let write_signatures = params.write_transactions as u64 * (1 + authority_signatures);
+    let final_signatures = if params.is_upgrade { 1 } else { 2 } + authority_signatures;
+    let extension_signatures = params.extension.is_some() as u64;
+    create_signatures + write_signatures + extension_signatures + final_signatures
+}
+
+pub fn transaction_count(params: &CostParams) -> usize {
+    params.write_transactions
+        + params.creates_buffer as usize
+        + params.extension.is_some() as usize
+        + 1
+}
+
+// the unit price is in micro lamports per compute unit
+pub fn priority_fee_per_tx(priority_fees: &PriorityFees) -> u64 {
+    (priority_fees.unit_limit as u64 * priority_fees.unit_price).div_ceil(1_000_000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(fee_payer: Pubkey, authority: Pubkey) -> CostParams {
+        CostParams {
+            program_len: 10_000,
+            max_program_len: 10_000,
+            extension: None,
+            is_upgrade: false,
+            creates_buffer: true,
+            write_transactions: 11,
+            priority_fees: PriorityFees {
+                unit_limit: 25_000,
+                unit_price: 550_000,
+            },
+            fee_payer,
+            authority,
+            refund_recipient: fee_payer,
+        }
+    }
+
+    #[test]
+    fn deploy_signed_by_the_authority_alone() {
+        let authority = Pubkey::new_unique();
+        let params = params(authority, authority);
+        // create 2, writes 11 x 1, deploy 2
+        assert_eq!(signature_count(&params), 15);
+        assert_eq!(transaction_count(&params), 13);
+    }
+
+    #[test]
+    fn deploy_with_a_separate_fee_payer() {
+        let params = params(Pubkey::new_unique(), Pubkey::new_unique());
+        // create 2, writes 11 x 2, deploy 3
+        assert_eq!(signature_count(&params), 27);
+        assert_eq!(transaction_count(&params), 13);
+    }
+
+    #[test]
+    fn upgrade_of_a_resumed_buffer_with_an_extension() {
+        let authority = Pubkey::new_unique();
+        let params = CostParams {
+            is_upgrade: true,
+            creates_buffer: false,
+            write_transactions: 3,
+            extension: Some(ProgramExtension {
+                additional_bytes: 2_048,
+                rent: 14_000_000,
+            }),
+            ..params(authority, authority)
+        };
+        // writes 3 x 1, extension 1, upgrade 1
+        assert_eq!(signature_count(&params), 5);
+        assert_eq!(transaction_count(&params), 5);
+    }
+
+    #[test]
+    fn upgrade_with_a_separate_fee_payer() {
+        let params = CostParams {
+            is_upgrade: true,
+            write_transactions: 0,
+            ..params(Pubkey::new_unique(), Pubkey::new_unique())
+        };
+        // create 2, upgrade 2
+        assert_eq!(signature_count(&params), 4);
+        assert_eq!(transaction_count(&params), 2);
+    }
+}
@@ -0,0 +1,1672 @@
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::{
+    rpc_client::SerializableTransaction,
+    rpc_config::{RpcAccountInfoConfig, RpcSendTransactionConfig},
+};
+use solana_sdk::bpf_loader_upgradeable::{
+    self, deploy_with_max_program_len, set_upgrade_authority, upgrade, UpgradeableLoaderState,
+};
+use solana_sdk::nonce::state::State as NonceState;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction::MAX_PERMITTED_DATA_LENGTH;
+use solana_sdk::transaction::Transaction;
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+use solana_transaction_status::UiTransactionEncoding;
+use std::{
+    fmt, fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+use tokio::{spawn, time};
+
+use crate::cost::{estimate_cost, CostEstimate, CostParams};
+use crate::errors::Error;
+use crate::fees::{
+    estimate_unit_price, next_unit_price, simulate_compute_units, unit_limit_with_margin,
+    UnitLimitMode, UnitPriceMode, MAX_COMPUTE_UNIT_LIMIT,
+};
+use crate::inspect::{fetch_executable, inspect_program, ProgramInfo};
+use crate::instructions::{
+    advance_nonce, close_buffer, create_buffer_account, create_nonce, deploy_program,
+    extend_program_data, nonce_message, nonce_transaction, set_new_buffer_auth,
+    set_program_upgrade_authority, set_program_upgrade_authority_checked, upgrade_program,
+    upgrade_program_message, write_data, LoaderSigners, PriorityFees,
+};
+use crate::keypair::save_buffer_keypair;
+use crate::multisig::UpgradeProposal;
+use crate::nonce::{get_nonce_info, NonceInfo};
+use crate::offline::{OfflineAction, OfflineTransaction};
+use crate::transactions::{send_tx_and_verify_status, SerializedMessage};
+use crate::verify::{verify_program, ProgramVerification};
+
+pub const MAX_WRITE_LEN: usize = 960;
+// a fee payer other than the authority adds its signature and account key to every write
+pub const FEE_PAYER_WRITE_OVERHEAD: usize = 96;
+pub const PROGRAM_EXTRA_SPACE: usize = 45;
+// bytes requested at a time when a buffer is read back
+pub const BUFFER_SLICE_LEN: usize = 256 * 1024;
+// times the mismatched ranges are written again before giving up
+pub const MAX_REWRITE_ROUNDS: usize = 3;
+
+pub const SEND_CFG: RpcSendTransactionConfig = RpcSendTransactionConfig {
+    skip_preflight: true,
+    preflight_commitment: Some(CommitmentLevel::Finalized),
+    encoding: Some(UiTransactionEncoding::Base64),
+    max_retries: Some(3),
+    min_context_slot: None,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Progress {
+    Idle,
+    // where the new buffer keypair was saved, before any lamports move into it
+    BufferKeypairSaved { path: PathBuf },
+    Sending { sent: usize, total: usize },
+    // the writes didn't land in time, they're sent again with a higher unit price
+    Retrying { unit_price: u64 },
+    // every write landed, the buffer is read back and compared with the program
+    Verifying,
+    // program byte ranges the buffer doesn't hold yet, they're written again
+    Rewriting { ranges: Vec<Range<usize>> },
+    Completed { buffer_address: Pubkey },
+}
+
+#[derive(Debug, Clone)]
+pub enum BufferTarget {
+    // a fresh buffer created from this keypair, the keypair is saved to the buffers dir first
+    New(Arc<Keypair>),
+    // an existing buffer owned by the authority, only the chunks that differ are written
+    Resume(Pubkey),
+}
+
+impl BufferTarget {
+    pub fn pubkey(&self) -> Pubkey {
+        match self {
+            BufferTarget::New(buffer_account) => buffer_account.pubkey(),
+            BufferTarget::Resume(buffer_address) => *buffer_address,
+        }
+    }
+}
+
+// space reserved for the program in the programdata account, anything above the current
+// binary lets later upgrades grow without extending the account
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum MaxProgramLen {
+    // sized exactly to the binary being deployed
+    #[default]
+    Exact,
+    Bytes(usize),
+    // relative to the binary being deployed, like 2x
+    Multiplier(f64),
+}
+
+impl MaxProgramLen {
+    pub fn resolve(&self, program_len: usize) -> Result<usize, Error> {
+        let max_program_len = match self {
+            MaxProgramLen::Exact => program_len,
+            MaxProgramLen::Bytes(bytes) => *bytes,
+            MaxProgramLen::Multiplier(multiplier) => {
+                (program_len as f64 * multiplier).ceil() as usize
+            }
+        };
+
+        let max_allowed = MAX_PERMITTED_DATA_LENGTH as usize
+            - UpgradeableLoaderState::size_of_programdata_metadata();
+        if max_program_len < program_len || max_program_len > max_allowed {
+            return Err(Error::InvalidProgramLen);
+        }
+        Ok(max_program_len)
+    }
+}
+
+// "" or "exact", a number of bytes, or a multiplier like "2x"
+impl FromStr for MaxProgramLen {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.is_empty() || value.eq_ignore_ascii_case("exact") {
+            return Ok(MaxProgramLen::Exact);
+        }
+        if let Some(multiplier) = value.strip_suffix(['x', 'X']) {
+            return multiplier
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|multiplier| *multiplier >= 1.0)
+                .map(MaxProgramLen::Multiplier)
+                .ok_or(Error::InvalidProgramLen);
+        }
+        value
+            .parse::<usize>()
+            .map(MaxProgramLen::Bytes)
+            .map_err(|_| Error::InvalidProgramLen)
+    }
+}
+
+impl fmt::Display for MaxProgramLen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaxProgramLen::Exact => write!(f, "exact"),
+            MaxProgramLen::Bytes(bytes) => write!(f, "{}", bytes),
+            MaxProgramLen::Multiplier(multiplier) => write!(f, "{}x", multiplier),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ProgramTarget {
+    // deploys need the program keypair to sign the program account creation
+    Keypair(Arc<Keypair>),
+    // an already deployed program, only upgrades are possible
+    Address(Pubkey),
+}
+
+impl ProgramTarget {
+    pub fn pubkey(&self) -> Pubkey {
+        match self {
+            ProgramTarget::Keypair(program_keypair) => program_keypair.pubkey(),
+            ProgramTarget::Address(program_address) => *program_address,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum UpgradeAuthorityChange {
+    Transfer(Pubkey),
+    // the new authority has to sign, it proves someone holds that key
+    TransferChecked(Arc<Keypair>),
+    // irreversible, the program can never be upgraded again
+    Revoke,
+}
+
+// room the programdata account is missing for a new binary, and the rent it costs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ProgramExtension {
+    pub additional_bytes: u32,
+    pub rent: u64,
+}
+
+// everything needed to build, sign and send the deploy transactions, progress is
+// reported through a plain callback so any frontend can drive it
+#[derive(Clone)]
+pub struct Deployer {
+    pub rpc_client: Arc<RpcClient>,
+    pub authority: Arc<Keypair>,
+    // pays the fees and the rent, the authority only signs where the loader requires it
+    pub payer: Arc<Keypair>,
+    pub priority_fees: PriorityFees,
+    pub unit_price_mode: UnitPriceMode,
+    pub unit_limit_mode: UnitLimitMode,
+    pub max_program_len: MaxProgramLen,
+    // extend the programdata account before an upgrade that doesn't fit, instead of failing
+    pub auto_extend: bool,
+    // the final deploy or upgrade uses this durable nonce instead of a recent blockhash
+    pub nonce_account: Option<Pubkey>,
+    // receives the buffer lamports after an upgrade, the payer when None
+    pub spill_address: Option<Pubkey>,
+}
+
+impl Deployer {
+    pub fn new(
+        rpc_client: Arc<RpcClient>,
+        authority: Arc<Keypair>,
+        priority_fees: PriorityFees,
+    ) -> Self {
+        Self {
+            rpc_client,
+            payer: authority.clone(),
+            authority,
+            priority_fees,
+            unit_price_mode: UnitPriceMode::Fixed,
+            unit_limit_mode: UnitLimitMode::default(),
+            max_program_len: MaxProgramLen::Exact,
+            auto_extend: false,
+            nonce_account: None,
+            spill_address: None,
+        }
+    }
+
+    pub fn with_spill_address(mut self, spill_address: Option<Pubkey>) -> Self {
+        self.spill_address = spill_address;
+        self
+    }
+
+    pub fn with_fee_payer(mut self, payer: Arc<Keypair>) -> Self {
+        self.payer = payer;
+        self
+    }
+
+    pub fn with_nonce_account(mut self, nonce_account: Option<Pubkey>) -> Self {
+        self.nonce_account = nonce_account;
+        self
+    }
+
+    pub fn with_auto_extend(mut self, auto_extend: bool) -> Self {
+        self.auto_extend = auto_extend;
+        self
+    }
+
+    pub fn with_max_program_len(mut self, max_program_len: MaxProgramLen) -> Self {
+        self.max_program_len = max_program_len;
+        self
+    }
+
+    pub fn with_unit_limit_mode(mut self, unit_limit_mode: UnitLimitMode) -> Self {
+        self.unit_limit_mode = unit_limit_mode;
+        self
+    }
+
+    pub fn with_unit_price_mode(mut self, unit_price_mode: UnitPriceMode) -> Self {
+        self.unit_price_mode = unit_price_mode;
+        self
+    }
+
+    pub fn spill_address(&self) -> Pubkey {
+        self.spill_address.unwrap_or(self.payer.pubkey())
+    }
+
+    // bytes per write, smaller when the writes carry the fee payer signature too
+    pub fn write_len(&self) -> usize {
+        match self.payer.pubkey() == self.authority.pubkey() {
+            true => MAX_WRITE_LEN,
+            false => MAX_WRITE_LEN - FEE_PAYER_WRITE_OVERHEAD,
+        }
+    }
+
+    // accounts whose recent fees are relevant for the buffer writes
+    fn fee_accounts(&self, buffer_address: &Pubkey) -> [Pubkey; 3] {
+        [
+            bpf_loader_upgradeable::id(),
+            *buffer_address,
+            self.payer.pubkey(),
+        ]
+    }
+
+    // in auto mode the configured unit price is the maximum to pay
+    async fn initial_priority_fees(&self, buffer_address: &Pubkey) -> Result<PriorityFees, Error> {
+        match self.unit_price_mode {
+            UnitPriceMode::Fixed => Ok(self.priority_fees),
+            UnitPriceMode::Auto { percentile } => {
+                let estimate = estimate_unit_price(
+                    &self.rpc_client,
+                    &self.fee_accounts(buffer_address),
+                    percentile,
+                )
+                .await?;
+                Ok(PriorityFees {
+                    unit_price: estimate.min(self.priority_fees.unit_price),
+                    ..self.priority_fees
+                })
+            }
+        }
+    }
+
+    // simulates the transaction once with the maximum limit and sizes the limit from the
+    // consumed units, the same closure builds the probe and the transaction that is sent
+    async fn simulated_priority_fees<B>(
+        &self,
+        priority_fees: PriorityFees,
+        build: B,
+    ) -> Result<PriorityFees, Error>
+    where
+        B: Fn(&PriorityFees) -> Result<Transaction, Error>,
+    {
+        let margin_percent = match self.unit_limit_mode {
+            UnitLimitMode::Fixed => return Ok(priority_fees),
+            UnitLimitMode::Simulated { margin_percent } => margin_percent,
+        };
+
+        let probe = build(&PriorityFees {
+            unit_limit: MAX_COMPUTE_UNIT_LIMIT,
+            ..priority_fees
+        })?;
+        let priority_fees = match simulate_compute_units(&self.rpc_client, &probe).await {
+            Some(units_consumed) => PriorityFees {
+                unit_limit: unit_limit_with_margin(units_consumed, margin_percent),
+                ..priority_fees
+            },
+            None => priority_fees,
+        };
+        Ok(priority_fees)
+    }
+
+    // price and limit for a one off transaction that touches the given account
+    async fn single_tx_priority_fees<B>(
+        &self,
+        account: &Pubkey,
+        build: B,
+    ) -> Result<PriorityFees, Error>
+    where
+        B: Fn(&PriorityFees) -> Result<Transaction, Error>,
+    {
+        let priority_fees = self.initial_priority_fees(account).await?;
+        self.simulated_priority_fees(priority_fees, build).await
+    }
+
+    // None in fixed mode, the failed transactions are sent again with the same fees
+    async fn retry_priority_fees(
+        &self,
+        buffer_address: &Pubkey,
+        previous_fees: &PriorityFees,
+    ) -> Option<PriorityFees> {
+        match self.unit_price_mode {
+            UnitPriceMode::Fixed => None,
+            UnitPriceMode::Auto { percentile } => {
+                // a failed estimate still bumps the previous price
+                let estimate = estimate_unit_price(
+                    &self.rpc_client,
+                    &self.fee_accounts(buffer_address),
+                    percentile,
+                )
+                .await
+                .unwrap_or(0);
+                Some(PriorityFees {
+                    unit_price: next_unit_price(
+                        estimate,
+                        previous_fees.unit_price,
+                        self.priority_fees.unit_price,
+                    ),
+                    ..*previous_fees
+                })
+            }
+        }
+    }
+
+    // creates (or resumes) the buffer and writes the program to it, retrying the writes
+    // that don't land until every chunk is confirmed
+    pub async fn write_buffer<F>(
+        &self,
+        buffer: BufferTarget,
+        program_bytes: &Vec<u8>,
+        mut on_progress: F,
+    ) -> Result<Pubkey, Error>
+    where
+        F: FnMut(Progress) + Send,
+    {
+        on_progress(Progress::Idle);
+        let buffer_address = buffer.pubkey();
+
+        let authority = &self.authority;
+        let payer = &self.payer;
+        let signers = LoaderSigners {
+            payer: payer.as_ref(),
+            authority: authority.as_ref(),
+        };
+        let rpc_client = &self.rpc_client;
+        let write_len = self.write_len();
+
+        if program_bytes.is_empty() {
+            return Err(Error::InvalidProgramLen);
+        }
+
+        let mut priority_fees = self.initial_priority_fees(&buffer_address).await?;
+
+        let mut pending_chunks: Vec<usize> = match buffer {
+            BufferTarget::Resume(_) => {
+                let buffer_data =
+                    get_buffer_data(&rpc_client, &buffer_address, &authority.pubkey()).await?;
+                if buffer_data.len() < program_bytes.len() {
+                    return Err(Error::InvalidBufferAccount);
+                }
+                get_pending_chunks(&buffer_data, program_bytes, write_len)
+            }
+            BufferTarget::New(buffer_acc) => {
+                let (recent_blockhash, _) = rpc_client
+                    .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+                    .await
+                    .map_err(|e| Error::RpcError(e))?;
+
+                let lamports = rpc_client
+                    .get_minimum_balance_for_rent_exemption(
+                        program_bytes.len() + PROGRAM_EXTRA_SPACE,
+                    )
+                    .await
+                    .unwrap_or(0);
+
+                let balance = rpc_client
+                    .get_balance(&payer.pubkey())
+                    .await
+                    .map_err(|_| Error::FetchBalanceError)?;
+
+                if lamports >= balance {
+                    return Err(Error::InsufficientSolBalance);
+                }
+
+                // the keypair must be recoverable before any lamports are moved into the buffer
+                let keypair_path = save_buffer_keypair(&buffer_acc)?;
+                on_progress(Progress::BufferKeypairSaved { path: keypair_path });
+
+                let build_create_tx = |priority_fees: &PriorityFees| {
+                    create_buffer_account(
+                        &buffer_acc,
+                        &payer,
+                        &authority.pubkey(),
+                        lamports,
+                        program_bytes,
+                        recent_blockhash,
+                        priority_fees,
+                    )
+                    .map_err(|e| Error::InstructionError(e))
+                };
+                let create_fees = self
+                    .simulated_priority_fees(priority_fees, &build_create_tx)
+                    .await?;
+                let buffer_acc_init_tx = build_create_tx(&create_fees)?;
+
+                // nothing can be written to a buffer that wasn't created
+                send_tx_and_verify_status(&rpc_client, &buffer_acc_init_tx, SEND_CFG).await?;
+
+                (0..program_bytes.len().div_ceil(write_len)).collect()
+            }
+        };
+
+        let (updated_blockhash, mut last_valid_blockheight) = rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+
+        // every write has the same shape, so the first pending one is simulated for all of them
+        if let Some(first_chunk) = pending_chunks.first() {
+            priority_fees = self
+                .simulated_priority_fees(priority_fees, |priority_fees| {
+                    write_data(
+                        &buffer_address,
+                        program_bytes,
+                        signers,
+                        updated_blockhash,
+                        write_len,
+                        &[*first_chunk],
+                        priority_fees,
+                    )
+                    .into_iter()
+                    .next()
+                    .ok_or(Error::UnexpectedError)
+                })
+                .await?;
+        }
+
+        let mut write_data_txs = write_data(
+            &buffer_address,
+            program_bytes,
+            signers,
+            updated_blockhash,
+            write_len,
+            &pending_chunks,
+            &priority_fees,
+        );
+
+        let sleep_between_send = 15; // 15 ms to await between each send
+        let batch_size = 250;
+
+        let mut tx_sent = 0;
+        let mut rewrite_rounds = 0;
+        loop {
+            for transaction in &write_data_txs {
+                tx_sent += 1;
+
+                on_progress(Progress::Sending {
+                    sent: tx_sent,
+                    total: write_data_txs.len(),
+                });
+                let client = rpc_client.clone();
+                let tx = transaction.clone();
+                spawn(async move {
+                    let _ = client.send_transaction_with_config(&tx, SEND_CFG).await;
+                });
+                time::sleep(Duration::from_millis(sleep_between_send)).await;
+            }
+
+            let tx_signatures: Vec<Signature> = write_data_txs
+                .iter()
+                .map(|tx| *tx.get_signature())
+                .collect();
+
+            let mut tx_signatures_batches = get_vec_with_batched_data(batch_size, &tx_signatures);
+
+            let check_failed_tx_tasks: Vec<JoinHandle<Vec<Signature>>> = tx_signatures_batches
+                .iter_mut()
+                .map(|chunk_signature| {
+                    let rpc_client = rpc_client.clone();
+                    let mut chunk_signatures = chunk_signature.clone();
+
+                    spawn(async move {
+                        let mut retrys = 0;
+                        let mut tx_to_retry = Vec::new();
+                        let max_retrys = 10;
+
+                        while retrys < max_retrys {
+                            let status_vec = rpc_client
+                                .get_signature_statuses(&chunk_signatures)
+                                .await
+                                .ok()
+                                .map(|v| v.value)
+                                .unwrap_or_default();
+                            let mut failed_signatures = Vec::new();
+
+                            for (i, status) in status_vec.iter().enumerate() {
+                                if status.as_ref().map_or(true, |c| {
+                                    c.err.is_some()
+                                        || (c.confirmation_status.is_none()
+                                            && retrys == max_retrys - 1)
+                                }) {
+                                    failed_signatures.push(chunk_signatures[i]);
+                                }
+                            }
+                            chunk_signatures.retain(|signature| {
+                                let keep = !failed_signatures.contains(signature);
+                                if !keep {
+                                    tx_to_retry.push(*signature);
+                                }
+                                keep
+                            });
+
+                            if chunk_signatures.is_empty() {
+                                break;
+                            }
+
+                            time::sleep(Duration::from_millis(500)).await;
+                            retrys += 1;
+                        }
+                        tx_to_retry
+                    })
+                })
+                .collect();
+
+            let results: Vec<Vec<Signature>> = join_all(check_failed_tx_tasks)
+                .await
+                .into_iter()
+                .filter_map(Result::ok)
+                .collect();
+
+            let tx_to_retry: Vec<Signature> = results.into_iter().flatten().collect();
+
+            // write_data builds one transaction per pending chunk, in the same order
+            (pending_chunks, write_data_txs) = pending_chunks
+                .drain(..)
+                .zip(write_data_txs.drain(..))
+                .filter(|(_, tx)| tx_to_retry.contains(tx.get_signature()))
+                .unzip();
+
+            let retry_fees = match tx_to_retry.is_empty() {
+                true => None,
+                false => {
+                    self.retry_priority_fees(&buffer_address, &priority_fees)
+                        .await
+                }
+            };
+
+            let current_blockheight = rpc_client.get_block_height().await.unwrap_or_default();
+
+            if let Some(retry_fees) = retry_fees {
+                // the fee instructions change, so the transactions are built again
+                let (updated_blockhash, last_valid_block_height_updated) = rpc_client
+                    .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+                    .await
+                    .map_err(|e| Error::RpcError(e))?;
+
+                last_valid_blockheight = last_valid_block_height_updated;
+                priority_fees = retry_fees;
+                on_progress(Progress::Retrying {
+                    unit_price: priority_fees.unit_price,
+                });
+
+                write_data_txs = write_data(
+                    &buffer_address,
+                    program_bytes,
+                    signers,
+                    updated_blockhash,
+                    write_len,
+                    &pending_chunks,
+                    &priority_fees,
+                );
+            } else if current_blockheight >= last_valid_blockheight {
+                let (updated_blockhash, last_valid_block_height_updated) = rpc_client
+                    .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+                    .await
+                    .map_err(|e| Error::RpcError(e))?;
+
+                last_valid_blockheight = last_valid_block_height_updated;
+
+                for transaction in write_data_txs.iter_mut() {
+                    transaction.sign(&[payer.as_ref(), authority.as_ref()], updated_blockhash);
+                }
+            }
+
+            if tx_to_retry.is_empty() {
+                // the statuses only say the writes landed, the buffer itself has the last word
+                on_progress(Progress::Verifying);
+                let buffer_data =
+                    fetch_buffer_slices(rpc_client, &buffer_address, program_bytes.len()).await?;
+                let ranges = mismatched_ranges(&buffer_data, program_bytes);
+                if ranges.is_empty() {
+                    on_progress(Progress::Completed { buffer_address });
+                    break;
+                }
+                if rewrite_rounds == MAX_REWRITE_ROUNDS {
+                    return Err(Error::BufferMismatch(ranges));
+                }
+                rewrite_rounds += 1;
+                on_progress(Progress::Rewriting { ranges });
+
+                let (updated_blockhash, last_valid_block_height_updated) = rpc_client
+                    .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+                    .await
+                    .map_err(|e| Error::RpcError(e))?;
+
+                last_valid_blockheight = last_valid_block_height_updated;
+                pending_chunks = get_pending_chunks(&buffer_data, program_bytes, write_len);
+                write_data_txs = write_data(
+                    &buffer_address,
+                    program_bytes,
+                    signers,
+                    updated_blockhash,
+                    write_len,
+                    &pending_chunks,
+                    &priority_fees,
+                );
+            }
+
+            tx_sent = 0;
+        }
+        Ok(buffer_address)
+    }
+
+    // upgrades the program if its account already exists, otherwise deploys it
+    pub async fn deploy_or_upgrade(
+        &self,
+        program: &ProgramTarget,
+        buffer_address: &Pubkey,
+        program_bytes: &Vec<u8>,
+    ) -> Result<Signature, Error> {
+        let rpc_client = &self.rpc_client;
+        let program_address = program.pubkey();
+
+        // if its err means that the account is not initialized yet and there is no data related to
+        let has_data = self.is_program_deployed(&program_address).await;
+
+        let program_keypair = match program {
+            ProgramTarget::Keypair(program_keypair) => Some(program_keypair),
+            ProgramTarget::Address(_) => None,
+        };
+        if !has_data && program_keypair.is_none() {
+            return Err(Error::ProgramAccountNotLoaded);
+        }
+
+        let (blockhash, _) = rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+
+        // only a deploy sizes the programdata account, an upgrade keeps (or extends) the current one
+        let max_program_len = match has_data {
+            true => program_bytes.len(),
+            false => self.max_program_len.resolve(program_bytes.len())?,
+        };
+
+        // only a deploy creates the program account, the loader funds the programdata
+        // account itself with the buffer lamports
+        let lamports = match has_data {
+            true => 0,
+            false => rpc_client
+                .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_program())
+                .await
+                .unwrap_or(0),
+        };
+
+        if has_data {
+            self.verify_upgrade_authority(&program_address).await?;
+            self.extend_if_needed(&program_address, program_bytes.len())
+                .await?;
+        }
+
+        let nonce = match &self.nonce_account {
+            Some(nonce_address) => Some(self.loaded_nonce(nonce_address).await?),
+            None => None,
+        };
+
+        let build_tx = |priority_fees: &PriorityFees| -> Result<Transaction, Error> {
+            let authority_address = &self.authority.pubkey();
+            if let Some(nonce) = &nonce {
+                return match (has_data, program_keypair) {
+                    (true, _) => Ok(nonce_transaction(
+                        vec![upgrade(
+                            &program_address,
+                            buffer_address,
+                            authority_address,
+                            &self.spill_address(),
+                        )],
+                        &self.payer,
+                        &[self.authority.as_ref()],
+                        nonce,
+                        priority_fees,
+                    )),
+                    (false, Some(program_keypair)) => Ok(nonce_transaction(
+                        deploy_with_max_program_len(
+                            &self.payer.pubkey(),
+                            &program_keypair.pubkey(),
+                            buffer_address,
+                            authority_address,
+                            lamports,
+                            max_program_len,
+                        )?,
+                        &self.payer,
+                        &[self.authority.as_ref(), program_keypair.as_ref()],
+                        nonce,
+                        priority_fees,
+                    )),
+                    (false, None) => Err(Error::ProgramAccountNotLoaded),
+                };
+            }
+
+            // so, if has data, we just upgrade the program
+            if has_data {
+                Ok(upgrade_program(
+                    &program_address,
+                    buffer_address,
+                    &self.payer,
+                    &self.authority,
+                    &self.spill_address(),
+                    blockhash,
+                    priority_fees,
+                ))
+            } else {
+                // if not, we deploy, in this part the program keypair needs to sign
+                Ok(deploy_program(
+                    LoaderSigners {
+                        payer: self.payer.as_ref(),
+                        authority: self.authority.as_ref(),
+                    },
+                    program_keypair.ok_or(Error::ProgramAccountNotLoaded)?,
+                    buffer_address,
+                    max_program_len,
+                    lamports,
+                    blockhash,
+                    priority_fees,
+                )?)
+            }
+        };
+        let priority_fees = self
+            .single_tx_priority_fees(buffer_address, &build_tx)
+            .await?;
+        let tx = build_tx(&priority_fees)?;
+        let signature = send_tx_and_verify_status(&rpc_client, &tx, SEND_CFG).await?;
+        Ok(signature)
+    }
+
+    // pre-flight estimate of everything the write and the deploy (or upgrade) will spend
+    pub async fn estimate_cost(
+        &self,
+        buffer: &BufferTarget,
+        program_address: Option<&Pubkey>,
+        program_bytes: &Vec<u8>,
+    ) -> Result<CostEstimate, Error> {
+        if program_bytes.is_empty() {
+            return Err(Error::InvalidProgramLen);
+        }
+
+        let (creates_buffer, write_transactions) = match buffer {
+            BufferTarget::New(_) => (true, program_bytes.len().div_ceil(self.write_len())),
+            BufferTarget::Resume(buffer_address) => {
+                let buffer_data =
+                    get_buffer_data(&self.rpc_client, buffer_address, &self.authority.pubkey())
+                        .await?;
+                let pending_chunks =
+                    get_pending_chunks(&buffer_data, program_bytes, self.write_len());
+                (false, pending_chunks.len())
+            }
+        };
+
+        // without a program account it's priced as a fresh deploy
+        let is_upgrade = match program_address {
+            Some(program_address) => self.is_program_deployed(program_address).await,
+            None => false,
+        };
+        let extension = match (program_address, is_upgrade) {
+            (Some(program_address), true) => {
+                self.program_extension(program_address, program_bytes.len())
+                    .await?
+            }
+            _ => None,
+        };
+
+        let max_program_len = match is_upgrade {
+            true => program_bytes.len(),
+            false => self.max_program_len.resolve(program_bytes.len())?,
+        };
+
+        estimate_cost(
+            &self.rpc_client,
+            CostParams {
+                program_len: program_bytes.len(),
+                max_program_len,
+                extension,
+                is_upgrade,
+                creates_buffer,
+                write_transactions,
+                priority_fees: self.priority_fees,
+                fee_payer: self.payer.pubkey(),
+                authority: self.authority.pubkey(),
+                refund_recipient: match is_upgrade {
+                    true => self.spill_address(),
+                    false => self.payer.pubkey(),
+                },
+            },
+        )
+        .await
+    }
+
+    // reads the program account, resolves its programdata account and checks that the
+    // loaded authority is the one allowed to upgrade it, returns the programdata address
+    pub async fn verify_upgrade_authority(
+        &self,
+        program_address: &Pubkey,
+    ) -> Result<Pubkey, Error> {
+        self.verify_upgrade_authority_of(program_address, &self.authority.pubkey())
+            .await
+    }
+
+    // same check for an authority that isn't loaded, like a multisig vault
+    pub async fn verify_upgrade_authority_of(
+        &self,
+        program_address: &Pubkey,
+        authority: &Pubkey,
+    ) -> Result<Pubkey, Error> {
+        let program = self
+            .rpc_client
+            .get_account(program_address)
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+
+        if program.owner != bpf_loader_upgradeable::id() {
+            return Err(Error::InvalidProgramAccount);
+        }
+        let programdata_address =
+            match bincode::deserialize::<UpgradeableLoaderState>(&program.data) {
+                Ok(UpgradeableLoaderState::Program {
+                    programdata_address,
+                }) => programdata_address,
+                _ => return Err(Error::InvalidProgramAccount),
+            };
+
+        let programdata = self
+            .rpc_client
+            .get_account(&programdata_address)
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+
+        match bincode::deserialize::<UpgradeableLoaderState>(&programdata.data) {
+            Ok(UpgradeableLoaderState::ProgramData {
+                upgrade_authority_address: Some(upgrade_authority),
+                ..
+            }) => {
+                if upgrade_authority != *authority {
+                    return Err(Error::UpgradeAuthorityMismatch);
+                }
+                Ok(programdata_address)
+            }
+            Ok(UpgradeableLoaderState::ProgramData {
+                upgrade_authority_address: None,
+                ..
+            }) => Err(Error::ProgramImmutable),
+            _ => Err(Error::InvalidProgramAccount),
+        }
+    }
+
+    // None when the new binary fits in the current programdata allocation
+    pub async fn program_extension(
+        &self,
+        program_address: &Pubkey,
+        program_len: usize,
+    ) -> Result<Option<ProgramExtension>, Error> {
+        let programdata = self
+            .rpc_client
+            .get_account(&programdata_address(program_address))
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+
+        let allocated_len = programdata
+            .data
+            .len()
+            .saturating_sub(UpgradeableLoaderState::size_of_programdata_metadata());
+        if program_len <= allocated_len {
+            return Ok(None);
+        }
+
+        // the loader only charges what's missing for the new size to be rent exempt
+        let rent = self
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_programdata(
+                program_len,
+            ))
+            .await
+            .map_err(|e| Error::RpcError(e))?
+            .saturating_sub(programdata.lamports);
+
+        Ok(Some(ProgramExtension {
+            additional_bytes: (program_len - allocated_len) as u32,
+            rent,
+        }))
+    }
+
+    // sent as its own transaction before the upgrade, if the upgrade fails afterwards the
+    // account is already big enough and the next attempt skips the extension
+    async fn extend_if_needed(
+        &self,
+        program_address: &Pubkey,
+        program_len: usize,
+    ) -> Result<(), Error> {
+        let extension = match self.program_extension(program_address, program_len).await? {
+            Some(extension) => extension,
+            None => return Ok(()),
+        };
+        if !self.auto_extend {
+            return Err(Error::ProgramDataTooSmall(extension.additional_bytes));
+        }
+
+        let (blockhash, _) = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+        let build_tx = |priority_fees: &PriorityFees| -> Result<Transaction, Error> {
+            Ok(extend_program_data(
+                program_address,
+                &self.payer,
+                extension.additional_bytes,
+                blockhash,
+                priority_fees,
+            ))
+        };
+        let priority_fees = self
+            .single_tx_priority_fees(&programdata_address(program_address), &build_tx)
+            .await?;
+        let tx = build_tx(&priority_fees)?;
+        send_tx_and_verify_status(&self.rpc_client, &tx, SEND_CFG).await?;
+        Ok(())
+    }
+
+    // compares the deployed executable with the local binary, works for any program id
+    pub async fn verify_program(
+        &self,
+        program_address: &Pubkey,
+        program_bytes: &[u8],
+    ) -> Result<ProgramVerification, Error> {
+        verify_program(&self.rpc_client, program_address, program_bytes).await
+    }
+
+    pub async fn inspect_program(&self, program_address: &Pubkey) -> Result<ProgramInfo, Error> {
+        inspect_program(&self.rpc_client, program_address).await
+    }
+
+    // a program id, programdata or buffer address
+    pub async fn fetch_executable(&self, address: &Pubkey) -> Result<Vec<u8>, Error> {
+        fetch_executable(&self.rpc_client, address).await
+    }
+
+    pub async fn is_program_deployed(&self, program_address: &Pubkey) -> bool {
+        self.rpc_client.get_account(program_address).await.is_ok()
+    }
+
+    pub async fn set_buffer_authority(
+        &self,
+        buffer_address: &Pubkey,
+        new_authority: &Pubkey,
+    ) -> Result<Signature, Error> {
+        let rpc_client = &self.rpc_client;
+        let (recent_blockhash, _) = rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+        let build_tx = |priority_fees: &PriorityFees| -> Result<Transaction, Error> {
+            Ok(set_new_buffer_auth(
+                buffer_address,
+                &self.payer,
+                &self.authority,
+                recent_blockhash,
+                new_authority,
+                priority_fees,
+            ))
+        };
+        let priority_fees = self
+            .single_tx_priority_fees(buffer_address, &build_tx)
+            .await?;
+        let tx = build_tx(&priority_fees)?;
+        let signature = send_tx_and_verify_status(&rpc_client, &tx, SEND_CFG).await?;
+        Ok(signature)
+    }
+
+    // only the current upgrade authority can hand the program over or revoke it
+    pub async fn set_upgrade_authority(
+        &self,
+        program_address: &Pubkey,
+        change: &UpgradeAuthorityChange,
+    ) -> Result<Signature, Error> {
+        let programdata_address = self.verify_upgrade_authority(program_address).await?;
+
+        let (recent_blockhash, _) = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+
+        let build_tx = |priority_fees: &PriorityFees| -> Result<Transaction, Error> {
+            let tx = match change {
+                UpgradeAuthorityChange::Transfer(new_authority) => set_program_upgrade_authority(
+                    program_address,
+                    &self.payer,
+                    &self.authority,
+                    Some(new_authority),
+                    recent_blockhash,
+                    priority_fees,
+                ),
+                UpgradeAuthorityChange::TransferChecked(new_authority) => {
+                    set_program_upgrade_authority_checked(
+                        program_address,
+                        &self.payer,
+                        &self.authority,
+                        new_authority,
+                        recent_blockhash,
+                        priority_fees,
+                    )
+                }
+                UpgradeAuthorityChange::Revoke => set_program_upgrade_authority(
+                    program_address,
+                    &self.payer,
+                    &self.authority,
+                    None,
+                    recent_blockhash,
+                    priority_fees,
+                ),
+            };
+            Ok(tx)
+        };
+        let priority_fees = self
+            .single_tx_priority_fees(&programdata_address, &build_tx)
+            .await?;
+        let tx = build_tx(&priority_fees)?;
+        let signature = send_tx_and_verify_status(&self.rpc_client, &tx, SEND_CFG).await?;
+        Ok(signature)
+    }
+
+    // the vault has to be both the buffer and the upgrade authority, otherwise the
+    // proposal would fail once the multisig executes it
+    pub async fn upgrade_proposal(
+        &self,
+        program_address: &Pubkey,
+        buffer_address: &Pubkey,
+        vault_address: &Pubkey,
+        spill_address: &Pubkey,
+    ) -> Result<UpgradeProposal, Error> {
+        get_buffer_data(&self.rpc_client, buffer_address, vault_address).await?;
+        self.verify_upgrade_authority_of(program_address, vault_address)
+            .await?;
+
+        let (recent_blockhash, _) = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+        let message = upgrade_program_message(
+            program_address,
+            buffer_address,
+            vault_address,
+            spill_address,
+            recent_blockhash,
+        );
+
+        Ok(UpgradeProposal {
+            program_address: *program_address,
+            buffer_address: *buffer_address,
+            vault_address: *vault_address,
+            spill_address: *spill_address,
+            message: SerializedMessage::from(&message),
+        })
+    }
+
+    pub async fn nonce_info(&self, nonce_address: &Pubkey) -> Result<NonceInfo, Error> {
+        get_nonce_info(&self.rpc_client, nonce_address).await
+    }
+
+    // a nonce the payer or the authority can advance, so the loaded keypairs can sign alone
+    async fn loaded_nonce(&self, nonce_address: &Pubkey) -> Result<NonceInfo, Error> {
+        let nonce = self.nonce_info(nonce_address).await?;
+        if nonce.authority != self.authority.pubkey() && nonce.authority != self.payer.pubkey() {
+            return Err(Error::NonceAuthorityMismatch);
+        }
+        Ok(nonce)
+    }
+
+    // the payer funds the nonce account and the loaded authority becomes its nonce authority
+    pub async fn create_nonce_account(&self, nonce_account: &Keypair) -> Result<Signature, Error> {
+        let lamports = self
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(NonceState::size())
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+        let (recent_blockhash, _) = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+        let build_tx = |priority_fees: &PriorityFees| -> Result<Transaction, Error> {
+            Ok(create_nonce(
+                nonce_account,
+                &self.payer,
+                &self.authority.pubkey(),
+                lamports,
+                recent_blockhash,
+                priority_fees,
+            ))
+        };
+        let priority_fees = self
+            .single_tx_priority_fees(&nonce_account.pubkey(), &build_tx)
+            .await?;
+        let tx = build_tx(&priority_fees)?;
+        send_tx_and_verify_status(&self.rpc_client, &tx, SEND_CFG).await
+    }
+
+    // discards anything signed with the current nonce, like an offline message that leaked
+    pub async fn advance_nonce(&self, nonce_address: &Pubkey) -> Result<Signature, Error> {
+        let nonce = self.loaded_nonce(nonce_address).await?;
+        let nonce_authority = match nonce.authority == self.payer.pubkey() {
+            true => &self.payer,
+            false => &self.authority,
+        };
+        let (recent_blockhash, _) = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+        let build_tx = |priority_fees: &PriorityFees| -> Result<Transaction, Error> {
+            Ok(advance_nonce(
+                nonce_address,
+                &self.payer,
+                nonce_authority,
+                recent_blockhash,
+                priority_fees,
+            ))
+        };
+        let priority_fees = self
+            .single_tx_priority_fees(nonce_address, &build_tx)
+            .await?;
+        let tx = build_tx(&priority_fees)?;
+        send_tx_and_verify_status(&self.rpc_client, &tx, SEND_CFG).await
+    }
+
+    // builds the action for an authority that signs offline, the loaded payer pays for it
+    // and the loaded authority only signs when it's also the nonce authority
+    pub async fn offline_transaction(
+        &self,
+        action: &OfflineAction,
+        offline_authority: &Pubkey,
+        nonce_address: &Pubkey,
+    ) -> Result<OfflineTransaction, Error> {
+        let nonce = get_nonce_info(&self.rpc_client, nonce_address).await?;
+
+        let (ixs, fee_account, program_keypair) = match action {
+            OfflineAction::Deploy {
+                program_keypair,
+                buffer_address,
+            } => {
+                if self.is_program_deployed(&program_keypair.pubkey()).await {
+                    return Err(Error::ProgramAlreadyDeployed);
+                }
+                let buffer_data =
+                    get_buffer_data(&self.rpc_client, buffer_address, offline_authority).await?;
+                let lamports = self
+                    .rpc_client
+                    .get_minimum_balance_for_rent_exemption(
+                        UpgradeableLoaderState::size_of_program(),
+                    )
+                    .await
+                    .map_err(|e| Error::RpcError(e))?;
+                let ixs = deploy_with_max_program_len(
+                    &self.payer.pubkey(),
+                    &program_keypair.pubkey(),
+                    buffer_address,
+                    offline_authority,
+                    lamports,
+                    self.max_program_len.resolve(buffer_data.len())?,
+                )?;
+                (ixs, *buffer_address, Some(program_keypair))
+            }
+            OfflineAction::Upgrade {
+                program_address,
+                buffer_address,
+            } => {
+                get_buffer_data(&self.rpc_client, buffer_address, offline_authority).await?;
+                let programdata_address = self
+                    .verify_upgrade_authority_of(program_address, offline_authority)
+                    .await?;
+                let upgrade_ix = upgrade(
+                    program_address,
+                    buffer_address,
+                    offline_authority,
+                    &self.spill_address(),
+                );
+                (vec![upgrade_ix], programdata_address, None)
+            }
+            OfflineAction::SetUpgradeAuthority {
+                program_address,
+                new_authority,
+            } => {
+                let programdata_address = self
+                    .verify_upgrade_authority_of(program_address, offline_authority)
+                    .await?;
+                let set_authority_ix = set_upgrade_authority(
+                    program_address,
+                    offline_authority,
+                    new_authority.as_ref(),
+                );
+                (vec![set_authority_ix], programdata_address, None)
+            }
+        };
+
+        // the simulation doesn't verify signatures, so the unsigned message can be sized
+        let build_tx = |priority_fees: &PriorityFees| -> Result<Transaction, Error> {
+            Ok(Transaction::new_unsigned(nonce_message(
+                ixs.clone(),
+                &self.payer.pubkey(),
+                &nonce,
+                priority_fees,
+            )))
+        };
+        let priority_fees = self
+            .single_tx_priority_fees(&fee_account, &build_tx)
+            .await?;
+        let message = nonce_message(ixs, &self.payer.pubkey(), &nonce, &priority_fees);
+
+        let mut local_signers = vec![self.payer.as_ref(), self.authority.as_ref()];
+        if let Some(program_keypair) = program_keypair {
+            local_signers.push(program_keypair.as_ref());
+        }
+        OfflineTransaction::new(message, &local_signers)
+    }
+
+    pub async fn send_offline_transaction(
+        &self,
+        offline_transaction: &OfflineTransaction,
+    ) -> Result<Signature, Error> {
+        let missing_signers = offline_transaction.missing_signers();
+        if !missing_signers.is_empty() {
+            return Err(Error::MissingSignatures(missing_signers));
+        }
+        send_tx_and_verify_status(&self.rpc_client, &offline_transaction.transaction, SEND_CFG)
+            .await
+    }
+
+    // closes a buffer owned by the authority and sends its lamports to the recipient
+    pub async fn close_buffer(
+        &self,
+        buffer_address: &Pubkey,
+        recipient: &Pubkey,
+    ) -> Result<Signature, Error> {
+        let rpc_client = &self.rpc_client;
+        let payer = &self.payer;
+        let authority = &self.authority;
+
+        // only buffers owned by the loaded authority can be closed
+        get_buffer_data(&rpc_client, buffer_address, &authority.pubkey()).await?;
+
+        let (recent_blockhash, _) = rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| Error::RpcError(e))?;
+
+        let build_tx = |priority_fees: &PriorityFees| -> Result<Transaction, Error> {
+            Ok(close_buffer(
+                buffer_address,
+                recipient,
+                payer,
+                authority,
+                recent_blockhash,
+                priority_fees,
+            ))
+        };
+        let priority_fees = self
+            .single_tx_priority_fees(buffer_address, &build_tx)
+            .await?;
+        let tx = build_tx(&priority_fees)?;
+        let signature = send_tx_and_verify_status(&rpc_client, &tx, SEND_CFG).await?;
+        Ok(signature)
+    }
+}
+
+pub fn get_program_bytes(program_path: &str) -> Result<Vec<u8>, Error> {
+    match fs::read(program_path) {
+        Ok(bytes) => {
+            if bytes.is_empty() {
+                return Err(Error::InvalidProgramLen);
+            }
+            Ok(bytes)
+        }
+        Err(_) => Err(Error::UnexpectedError),
+    }
+}
+
+// the other way around, for an executable dumped from chain
+pub fn save_program_bytes(program_path: &Path, program_bytes: &[u8]) -> Result<(), Error> {
+    if program_bytes.is_empty() {
+        return Err(Error::InvalidProgramLen);
+    }
+    fs::write(program_path, program_bytes).map_err(|_| Error::ProgramWriteError)
+}
+
+pub fn programdata_address(program_address: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_address.as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
+// fetch a buffer account and return the program data stored after the loader header,
+// verifying that the account is a buffer owned by the given authority
+pub async fn get_buffer_data(
+    rpc_client: &RpcClient,
+    buffer_address: &Pubkey,
+    authority: &Pubkey,
+) -> Result<Vec<u8>, Error> {
+    let account = rpc_client
+        .get_account(buffer_address)
+        .await
+        .map_err(|e| Error::RpcError(e))?;
+
+    if account.owner != bpf_loader_upgradeable::id() {
+        return Err(Error::InvalidBufferAccount);
+    }
+
+    match bincode::deserialize::<UpgradeableLoaderState>(&account.data) {
+        Ok(UpgradeableLoaderState::Buffer { authority_address }) => {
+            if authority_address != Some(*authority) {
+                return Err(Error::BufferAuthorityMismatch);
+            }
+        }
+        _ => return Err(Error::InvalidBufferAccount),
+    }
+
+    let header_len = UpgradeableLoaderState::size_of_buffer_metadata();
+    Ok(account.data[header_len..].to_vec())
+}
+
+// the first `len` bytes stored in a buffer after the loader header, big buffers are read in
+// slices so no single response gets too large
+pub async fn fetch_buffer_slices(
+    rpc_client: &RpcClient,
+    buffer_address: &Pubkey,
+    len: usize,
+) -> Result<Vec<u8>, Error> {
+    let header_len = UpgradeableLoaderState::size_of_buffer_metadata();
+    let slices = buffer_slices(len).into_iter().map(|range| {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: Some(UiDataSliceConfig {
+                offset: header_len + range.start,
+                length: range.len(),
+            }),
+            commitment: Some(rpc_client.commitment()),
+            ..RpcAccountInfoConfig::default()
+        };
+        async move {
+            rpc_client
+                .get_account_with_config(buffer_address, config)
+                .await
+                .map_err(|e| Error::RpcError(e))?
+                .value
+                .map(|account| account.data)
+                .ok_or(Error::InvalidBufferAccount)
+        }
+    });
+
+    let mut buffer_data = Vec::with_capacity(len);
+    for slice in join_all(slices).await {
+        buffer_data.extend(slice?);
+    }
+    Ok(buffer_data)
+}
+
+// program byte ranges requested one at a time, the last one is shorter
+pub fn buffer_slices(len: usize) -> Vec<Range<usize>> {
+    (0..len)
+        .step_by(BUFFER_SLICE_LEN)
+        .map(|offset| offset..(offset + BUFFER_SLICE_LEN).min(len))
+        .collect()
+}
+
+// program byte ranges the buffer data doesn't match, missing bytes included
+pub fn mismatched_ranges(buffer_data: &[u8], program_bytes: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (offset, byte) in program_bytes.iter().enumerate() {
+        if buffer_data.get(offset) == Some(byte) {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(range) if range.end == offset => range.end += 1,
+            _ => ranges.push(offset..offset + 1),
+        }
+    }
+    ranges
+}
+
+// indexes of the program chunks that are not yet present in the buffer data
+pub fn get_pending_chunks(
+    buffer_data: &[u8],
+    program_bytes: &[u8],
+    bytes_per_chunk: usize,
+) -> Vec<usize> {
+    program_bytes
+        .chunks(bytes_per_chunk)
+        .enumerate()
+        .filter(|(index, chunk)| {
+            let offset = index * bytes_per_chunk;
+            buffer_data.get(offset..offset + chunk.len()) != Some(*chunk)
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+// lamports held by a buffer, the amount recovered when it gets closed
+pub async fn buffer_lamports(
+    rpc_client: Arc<RpcClient>,
+    buffer_address: Pubkey,
+) -> Result<u64, Error> {
+    let account = rpc_client
+        .get_account(&buffer_address)
+        .await
+        .map_err(|e| Error::RpcError(e))?;
+
+    if account.owner != bpf_loader_upgradeable::id() {
+        return Err(Error::InvalidBufferAccount);
+    }
+    Ok(account.lamports)
+}
+
+pub fn get_vec_with_batched_data<T: Clone>(batch_size: usize, base_vec: &Vec<T>) -> Vec<Vec<T>> {
+    let mut offset = 0;
+    let mut vec_with_batchs = Vec::new();
+
+    while offset < base_vec.len() {
+        let chunk_end = (offset + batch_size).min(base_vec.len());
+
+        let chunk = base_vec[offset..chunk_end].to_vec();
+
+        offset += batch_size;
+        vec_with_batchs.push(chunk);
+    }
+    vec_with_batchs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(len: usize) -> Vec<u8> {
+        (0..len).map(|index| (index % 251) as u8 + 1).collect()
+    }
+
+    #[test]
+    fn exact_buffer_has_nothing_pending() {
+        let program_bytes = program(3 * MAX_WRITE_LEN + 100);
+        assert!(mismatched_ranges(&program_bytes, &program_bytes).is_empty());
+        assert!(get_pending_chunks(&program_bytes, &program_bytes, MAX_WRITE_LEN).is_empty());
+    }
+
+    #[test]
+    fn buffer_zero_padding_past_the_program_is_ignored() {
+        let program_bytes = program(2 * MAX_WRITE_LEN);
+        let mut buffer_data = program_bytes.clone();
+        buffer_data.extend([0; 64]);
+        assert!(mismatched_ranges(&buffer_data, &program_bytes).is_empty());
+        assert!(get_pending_chunks(&buffer_data, &program_bytes, MAX_WRITE_LEN).is_empty());
+    }
+
+    #[test]
+    fn short_buffer_misses_the_tail() {
+        let program_bytes = program(3 * MAX_WRITE_LEN + 100);
+        let buffer_data = &program_bytes[..MAX_WRITE_LEN + 10];
+        assert_eq!(
+            mismatched_ranges(buffer_data, &program_bytes),
+            vec![MAX_WRITE_LEN + 10..program_bytes.len()]
+        );
+        // the chunk holding the first missing byte is written again too
+        assert_eq!(
+            get_pending_chunks(buffer_data, &program_bytes, MAX_WRITE_LEN),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn empty_buffer_misses_everything() {
+        let program_bytes = program(MAX_WRITE_LEN + 1);
+        assert_eq!(
+            mismatched_ranges(&[], &program_bytes),
+            vec![0..program_bytes.len()]
+        );
+        assert_eq!(
+            get_pending_chunks(&[], &program_bytes, MAX_WRITE_LEN),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn mismatch_across_a_chunk_boundary() {
+        let program_bytes = program(3 * MAX_WRITE_LEN);
+        let mut buffer_data = program_bytes.clone();
+        for byte in &mut buffer_data[MAX_WRITE_LEN - 2..MAX_WRITE_LEN + 2] {
+            *byte = 0;
+        }
+        assert_eq!(
+            mismatched_ranges(&buffer_data, &program_bytes),
+            vec![MAX_WRITE_LEN - 2..MAX_WRITE_LEN + 2]
+        );
+        assert_eq!(
+            get_pending_chunks(&buffer_data, &program_bytes, MAX_WRITE_LEN),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn separate_mismatches_stay_separate_ranges() {
+        let program_bytes = program(3 * MAX_WRITE_LEN);
+        let mut buffer_data = program_bytes.clone();
+        buffer_data[10] = 0;
+        buffer_data[11] = 0;
+        buffer_data[2 * MAX_WRITE_LEN + 5] = 0;
+        assert_eq!(
+            mismatched_ranges(&buffer_data, &program_bytes),
+            vec![10..12, 2 * MAX_WRITE_LEN + 5..2 * MAX_WRITE_LEN + 6]
+        );
+        assert_eq!(
+            get_pending_chunks(&buffer_data, &program_bytes, MAX_WRITE_LEN),
+            vec![0, 2]
+        );
+    }
+
+    #[test]
+    fn buffer_slices_cover_the_program_once() {
+        assert!(buffer_slices(0).is_empty());
+        assert_eq!(buffer_slices(100), vec![0..100]);
+        assert_eq!(
+            buffer_slices(2 * BUFFER_SLICE_LEN),
+            vec![0..BUFFER_SLICE_LEN, BUFFER_SLICE_LEN..2 * BUFFER_SLICE_LEN]
+        );
+        assert_eq!(
+            buffer_slices(2 * BUFFER_SLICE_LEN + 10),
+            vec![
+                0..BUFFER_SLICE_LEN,
+                BUFFER_SLICE_LEN..2 * BUFFER_SLICE_LEN,
+                2 * BUFFER_SLICE_LEN..2 * BUFFER_SLICE_LEN + 10
+            ]
+        );
+    }
+
+    #[test]
+    fn max_program_len_from_str() {
+        assert_eq!("".parse::<MaxProgramLen>().ok(), Some(MaxProgramLen::Exact));
+        assert_eq!(
+            " Exact ".parse::<MaxProgramLen>().ok(),
+            Some(MaxProgramLen::Exact)
+        );
+        assert_eq!(
+            "4096".parse::<MaxProgramLen>().ok(),
+            Some(MaxProgramLen::Bytes(4096))
+        );
+        assert_eq!(
+            "2x".parse::<MaxProgramLen>().ok(),
+            Some(MaxProgramLen::Multiplier(2.0))
+        );
+        assert_eq!(
+            "1.5 X".parse::<MaxProgramLen>().ok(),
+            Some(MaxProgramLen::Multiplier(1.5))
+        );
+        // a multiplier below 1 can't hold the binary
+        assert!("0.5x".parse::<MaxProgramLen>().is_err());
+        assert!("-10".parse::<MaxProgramLen>().is_err());
+        assert!("large".parse::<MaxProgramLen>().is_err());
+    }
+
+    #[test]
+    fn max_program_len_round_trips_through_display() {
+        for max_program_len in [
+            MaxProgramLen::Exact,
+            MaxProgramLen::Bytes(4096),
+            MaxProgramLen::Multiplier(2.5),
+        ] {
+            assert_eq!(
+                max_program_len.to_string().parse::<MaxProgramLen>().ok(),
+                Some(max_program_len)
+            );
+        }
+    }
+
+    #[test]
+    fn max_program_len_resolve() {
+        assert_eq!(MaxProgramLen::Exact.resolve(1000).ok(), Some(1000));
+        assert_eq!(MaxProgramLen::Bytes(4096).resolve(1000).ok(), Some(4096));
+        assert_eq!(
+            MaxProgramLen::Multiplier(1.5).resolve(1001).ok(),
+            Some(1502)
+        );
+    }
+
+    #[test]
+    fn max_program_len_resolve_rejects_lengths_out_of_range() {
+        // smaller than the binary
+        assert!(MaxProgramLen::Bytes(500).resolve(1000).is_err());
+        // larger than an account can be
+        assert!(MaxProgramLen::Bytes(MAX_PERMITTED_DATA_LENGTH as usize)
+            .resolve(1000)
+            .is_err());
+        assert!(MaxProgramLen::Multiplier(100.0)
+            .resolve(1024 * 1024)
+            .is_err());
+    }
+}
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use lich_deployer::errors::Error;
+use rfd::{AsyncFileDialog, FileHandle};
+
+pub enum FileType {
+    Keypair,
+    Program,
+    Config,
+}
+
+impl FileType {
+    fn extension(&self) -> &str {
+        match self {
+            FileType::Keypair => "json",
+            FileType::Program => "so",
+            FileType::Config => "yml",
+        }
+    }
+}
+
+pub async fn pick_file(file_type: FileType) -> Result<PathBuf, Error> {
+    let handle = AsyncFileDialog::new()
+        .set_title("Choose a valid file-type")
+        .pick_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+
+    check_file_extension(handle.clone(), file_type.extension())?;
+
+    Ok(handle.path().to_owned())
+}
+
+pub async fn save_file(file_type: FileType, file_name: &str) -> Result<PathBuf, Error> {
+    let handle = AsyncFileDialog::new()
+        .set_title("Choose where to save the file")
+        .set_file_name(file_name)
+        .save_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+
+    check_file_extension(handle.clone(), file_type.extension())?;
+
+    Ok(handle.path().to_owned())
+}
+
+fn check_file_extension(handle: FileHandle, extension: &str) -> Result<(), Error> {
+    if handle.path().extension().and_then(|ext| ext.to_str()) != Some(extension) {
+        return Err(Error::InvalidFileType);
+    }
+    Ok(())
+}
@@ -1,5 +1,7 @@
+use std::ops::Range;
+
 use solana_client::client_error::ClientError;
-use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+use solana_sdk::{instruction::InstructionError, pubkey::Pubkey, transaction::TransactionError};
 
 #[derive(Debug)]
 pub enum Error {
@@ -15,6 +17,38 @@ pub enum Error {
     TransactionConfirmationStatusFailed,
     InsufficientSolBalance,
     UndefinedNewBufferAuthority,
+    InvalidBufferAccount,
+    BufferAuthorityMismatch,
+    KeypairPersistError,
+    InvalidRecipient,
+    InvalidCliArguments(String),
+    InvalidKeypairFile,
+    ProgramAlreadyDeployed,
+    ProgramNotDeployed,
+    InvalidConfigFile,
+    ConfigWriteError,
+    // the additional bytes the programdata account needs for the new binary
+    ProgramDataTooSmall(u32),
+    InvalidProgramAccount,
+    ProgramImmutable,
+    UpgradeAuthorityMismatch,
+    UndefinedNewUpgradeAuthority,
+    ImmutableNotConfirmed,
+    InvalidMultisigAddress,
+    InvalidVaultAddress,
+    InvalidSpillAddress,
+    InvalidNonceAccount,
+    NonceAuthorityMismatch,
+    InvalidOfflineAuthority,
+    InvalidMessage,
+    InvalidSignature,
+    // signers of an offline transaction whose signature wasn't pasted back yet
+    MissingSignatures(Vec<Pubkey>),
+    // program byte ranges the buffer still didn't hold after writing them again
+    BufferMismatch(Vec<Range<usize>>),
+    // the deployed executable isn't the local binary
+    ProgramMismatch,
+    ProgramWriteError,
 }
 
 impl From<TransactionError> for Error {
@@ -51,6 +85,81 @@ impl Clone for Error {
             }
             Error::InsufficientSolBalance => Error::InsufficientSolBalance,
             Error::UndefinedNewBufferAuthority => Error::UndefinedNewBufferAuthority,
+            Error::InvalidBufferAccount => Error::InvalidBufferAccount,
+            Error::BufferAuthorityMismatch => Error::BufferAuthorityMismatch,
+            Error::KeypairPersistError => Error::KeypairPersistError,
+            Error::InvalidRecipient => Error::InvalidRecipient,
+            Error::InvalidCliArguments(e) => Error::InvalidCliArguments(e.clone()),
+            Error::InvalidKeypairFile => Error::InvalidKeypairFile,
+            Error::ProgramAlreadyDeployed => Error::ProgramAlreadyDeployed,
+            Error::ProgramNotDeployed => Error::ProgramNotDeployed,
+            Error::InvalidConfigFile => Error::InvalidConfigFile,
+            Error::ConfigWriteError => Error::ConfigWriteError,
+            Error::ProgramDataTooSmall(bytes) => Error::ProgramDataTooSmall(*bytes),
+            Error::InvalidProgramAccount => Error::InvalidProgramAccount,
+            Error::ProgramImmutable => Error::ProgramImmutable,
+            Error::UpgradeAuthorityMismatch => Error::UpgradeAuthorityMismatch,
+            Error::UndefinedNewUpgradeAuthority => Error::UndefinedNewUpgradeAuthority,
+            Error::ImmutableNotConfirmed => Error::ImmutableNotConfirmed,
+            Error::InvalidMultisigAddress => Error::InvalidMultisigAddress,
+            Error::InvalidVaultAddress => Error::InvalidVaultAddress,
+            Error::InvalidSpillAddress => Error::InvalidSpillAddress,
+            Error::InvalidNonceAccount => Error::InvalidNonceAccount,
+            Error::NonceAuthorityMismatch => Error::NonceAuthorityMismatch,
+            Error::InvalidOfflineAuthority => Error::InvalidOfflineAuthority,
+            Error::InvalidMessage => Error::InvalidMessage,
+            Error::InvalidSignature => Error::InvalidSignature,
+            Error::MissingSignatures(signers) => Error::MissingSignatures(signers.clone()),
+            Error::BufferMismatch(ranges) => Error::BufferMismatch(ranges.clone()),
+            Error::ProgramMismatch => Error::ProgramMismatch,
+            Error::ProgramWriteError => Error::ProgramWriteError,
+        }
+    }
+}
+
+impl Error {
+    // exit codes used by the command line mode
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::InvalidCliArguments(_) => 2,
+            Error::DialogClosed
+            | Error::InvalidFileType
+            | Error::InvalidKeypairFile
+            | Error::KeypairPersistError
+            | Error::InvalidProgramLen
+            | Error::InvalidConfigFile
+            | Error::ConfigWriteError
+            | Error::ProgramWriteError => 3,
+            Error::RpcError(_) | Error::FetchBalanceError => 4,
+            Error::TransactionError(_)
+            | Error::InstructionError(_)
+            | Error::TransactionConfirmationStatusFailed
+            | Error::BufferMismatch(_) => 5,
+            Error::InsufficientSolBalance => 6,
+            Error::ProgramAccountNotLoaded
+            | Error::ProgramAlreadyDeployed
+            | Error::ProgramNotDeployed
+            | Error::ProgramDataTooSmall(_)
+            | Error::InvalidProgramAccount
+            | Error::ProgramImmutable
+            | Error::ImmutableNotConfirmed
+            | Error::ProgramMismatch => 7,
+            Error::UndefinedNewBufferAuthority
+            | Error::InvalidBufferAccount
+            | Error::BufferAuthorityMismatch
+            | Error::UpgradeAuthorityMismatch
+            | Error::UndefinedNewUpgradeAuthority
+            | Error::InvalidRecipient
+            | Error::InvalidMultisigAddress
+            | Error::InvalidVaultAddress
+            | Error::InvalidSpillAddress
+            | Error::InvalidNonceAccount
+            | Error::NonceAuthorityMismatch
+            | Error::InvalidOfflineAuthority
+            | Error::InvalidMessage
+            | Error::InvalidSignature
+            | Error::MissingSignatures(_) => 8,
+            Error::UnexpectedError => 1,
         }
     }
 }
@@ -0,0 +1,172 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{pubkey::Pubkey, transaction::Transaction};
+
+use crate::errors::Error;
+
+// every retry round pays at least this much more than the previous one
+pub const RETRY_FEE_BUMP_PERCENT: u64 = 20;
+// a round that failed at a zero (or tiny) price is bumped from here instead, the percent bump
+// of zero is still zero
+pub const MIN_RETRY_UNIT_PRICE: u64 = 10_000;
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+pub const DEFAULT_UNIT_LIMIT_MARGIN: u32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UnitPriceMode {
+    // the configured unit price is used as is
+    #[default]
+    Fixed,
+    // the unit price follows a percentile of the recent prioritization fees, the configured
+    // unit price becomes the maximum the app is allowed to pay
+    Auto {
+        percentile: u8,
+    },
+}
+
+impl fmt::Display for UnitPriceMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnitPriceMode::Fixed => write!(f, "Fixed"),
+            UnitPriceMode::Auto { percentile } => write!(f, "Auto (p{})", percentile),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnitLimitMode {
+    // the configured unit limit is used for every transaction
+    Fixed,
+    // every transaction type is simulated once and gets the consumed units plus a margin,
+    // the configured unit limit is only used when the simulation fails
+    Simulated { margin_percent: u32 },
+}
+
+impl Default for UnitLimitMode {
+    fn default() -> Self {
+        UnitLimitMode::Simulated {
+            margin_percent: DEFAULT_UNIT_LIMIT_MARGIN,
+        }
+    }
+}
+
+impl fmt::Display for UnitLimitMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnitLimitMode::Fixed => write!(f, "Fixed"),
+            UnitLimitMode::Simulated { margin_percent } => {
+                write!(f, "Simulated (+{}%)", margin_percent)
+            }
+        }
+    }
+}
+
+// percentile of the fees paid by recent transactions that write to any of the accounts
+pub async fn estimate_unit_price(
+    rpc_client: &RpcClient,
+    accounts: &[Pubkey],
+    percentile: u8,
+) -> Result<u64, Error> {
+    let recent_fees = rpc_client
+        .get_recent_prioritization_fees(accounts)
+        .await
+        .map_err(|e| Error::RpcError(e))?;
+
+    let mut fees: Vec<u64> = recent_fees
+        .into_iter()
+        .map(|fee| fee.prioritization_fee)
+        .collect();
+    Ok(fee_percentile(&mut fees, percentile))
+}
+
+pub fn fee_percentile(fees: &mut Vec<u64>, percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    fees.sort_unstable();
+    let index = (fees.len() - 1) * percentile.min(100) as usize / 100;
+    fees[index]
+}
+
+// price for the next retry round: the fresh estimate, but never less than a bump over the
+// price that just failed to land, and never above the configured maximum
+pub fn next_unit_price(estimate: u64, previous_price: u64, max_unit_price: u64) -> u64 {
+    let previous_price = previous_price.max(MIN_RETRY_UNIT_PRICE);
+    let bumped = previous_price + previous_price * RETRY_FEE_BUMP_PERCENT / 100;
+    estimate.max(bumped).min(max_unit_price)
+}
+
+// units consumed by the transaction, None when the simulation fails so the caller can
+// fall back to the configured limit
+pub async fn simulate_compute_units(
+    rpc_client: &RpcClient,
+    transaction: &Transaction,
+) -> Option<u64> {
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let result = rpc_client
+        .simulate_transaction_with_config(transaction, config)
+        .await
+        .ok()?
+        .value;
+    if result.err.is_some() {
+        return None;
+    }
+    result.units_consumed
+}
+
+pub fn unit_limit_with_margin(units_consumed: u64, margin_percent: u32) -> u32 {
+    let units = units_consumed + units_consumed * margin_percent as u64 / 100;
+    units.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fee_percentile_of_no_fees_is_zero() {
+        assert_eq!(fee_percentile(&mut Vec::new(), 75), 0);
+    }
+
+    #[test]
+    fn fee_percentile_picks_from_the_sorted_fees() {
+        let mut fees = vec![500, 0, 100, 0, 300];
+        assert_eq!(fee_percentile(&mut fees, 0), 0);
+        assert_eq!(fee_percentile(&mut fees, 50), 100);
+        assert_eq!(fee_percentile(&mut fees, 75), 300);
+        assert_eq!(fee_percentile(&mut fees, 100), 500);
+        // clamped to the highest fee
+        assert_eq!(fee_percentile(&mut fees, 250), 500);
+    }
+
+    #[test]
+    fn next_unit_price_rises_from_zero() {
+        let first_retry = next_unit_price(0, 0, 1_000_000);
+        assert!(first_retry > 0);
+        let second_retry = next_unit_price(0, first_retry, 1_000_000);
+        assert!(second_retry > first_retry);
+    }
+
+    #[test]
+    fn next_unit_price_bumps_the_failed_price() {
+        assert_eq!(next_unit_price(0, 100_000, 1_000_000), 120_000);
+    }
+
+    #[test]
+    fn next_unit_price_follows_a_higher_estimate() {
+        assert_eq!(next_unit_price(500_000, 100_000, 1_000_000), 500_000);
+    }
+
+    #[test]
+    fn next_unit_price_never_goes_above_the_max() {
+        assert_eq!(next_unit_price(2_000_000, 100_000, 1_000_000), 1_000_000);
+        assert_eq!(next_unit_price(0, 900_000, 1_000_000), 1_000_000);
+        assert_eq!(next_unit_price(0, 0, 5_000), 5_000);
+    }
+}
@@ -1,44 +1,51 @@
-use std::{env, path::PathBuf};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 
 use crate::errors::Error;
-use rfd::{AsyncFileDialog, FileHandle};
 
 pub const DEFAULT_LOCATION: &str = ".config/solana/id.json";
+pub const BUFFERS_LOCATION: &str = ".config/lich-deployer/buffers";
+pub const CLUSTERS_LOCATION: &str = ".config/lich-deployer/clusters.json";
+pub const SETTINGS_LOCATION: &str = ".config/lich-deployer/settings.json";
 
-pub fn default_keypair_path() -> PathBuf {
+fn home_dir() -> PathBuf {
     let home_dir = env::var("HOME") // mac users
         .or_else(|_| env::var("USERPROFILE")) // windows users
         .expect("Cannot find home directory");
-    let mut path = PathBuf::from(home_dir);
+    PathBuf::from(home_dir)
+}
+
+pub fn default_keypair_path() -> PathBuf {
+    let mut path = home_dir();
     path.push(DEFAULT_LOCATION);
     path
 }
 
-pub enum FileType {
-    Keypair,
-    Program,
+// every buffer keypair generated by the app is stored here before the buffer is created
+pub fn buffers_dir() -> PathBuf {
+    let mut path = home_dir();
+    path.push(BUFFERS_LOCATION);
+    path
 }
 
-pub async fn pick_file(file_type: FileType) -> Result<PathBuf, Error> {
-    let handle = AsyncFileDialog::new()
-        .set_title("Choose a valid file-type")
-        .pick_file()
-        .await
-        .ok_or(Error::DialogClosed)?;
-
-    let file_extension = match file_type {
-        FileType::Keypair => String::from("json"),
-        FileType::Program => String::from("so"),
-    };
-
-    check_file_extension(handle.clone(), &file_extension)?;
+pub fn clusters_path() -> PathBuf {
+    let mut path = home_dir();
+    path.push(CLUSTERS_LOCATION);
+    path
+}
 
-    Ok(handle.path().to_owned())
+pub fn settings_path() -> PathBuf {
+    let mut path = home_dir();
+    path.push(SETTINGS_LOCATION);
+    path
 }
 
-fn check_file_extension(handle: FileHandle, extension: &str) -> Result<(), Error> {
-    if handle.path().extension().and_then(|ext| ext.to_str()) != Some(extension) {
-        return Err(Error::InvalidFileType);
+// creates the app config dir on the first write
+pub fn write_config_file(path: &Path, content: &str) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|_| Error::ConfigWriteError)?;
     }
-    Ok(())
+    fs::write(path, content).map_err(|_| Error::ConfigWriteError)
 }
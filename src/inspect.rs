@@ -0,0 +1,122 @@
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    account::Account,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    pubkey::Pubkey,
+};
+
+use crate::{
+    errors::Error,
+    verify::{deployed_executable, fetch_program_executable, sha256_hex, trim_zero_padding},
+};
+
+// read only view of an upgradeable program, serialized as is for scripts
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgramInfo {
+    pub program_address: String,
+    pub programdata_address: String,
+    // None once the program is immutable
+    pub upgrade_authority: Option<String>,
+    pub last_deployed_slot: u64,
+    // the executable without the zero padding, cut where the elf ends
+    pub data_len: usize,
+    // room for the executable in the programdata account, the max program length
+    pub allocated_len: usize,
+    // lamports held by the programdata account
+    pub rent_balance: u64,
+    pub sha256: String,
+}
+
+// resolves the programdata account through the program account
+pub async fn fetch_programdata(
+    rpc_client: &RpcClient,
+    program_address: &Pubkey,
+) -> Result<(Pubkey, Account), Error> {
+    let program = rpc_client
+        .get_account(program_address)
+        .await
+        .map_err(|e| Error::RpcError(e))?;
+
+    if program.owner != bpf_loader_upgradeable::id() {
+        return Err(Error::InvalidProgramAccount);
+    }
+    let programdata_address = match bincode::deserialize::<UpgradeableLoaderState>(&program.data) {
+        Ok(UpgradeableLoaderState::Program {
+            programdata_address,
+        }) => programdata_address,
+        _ => return Err(Error::InvalidProgramAccount),
+    };
+
+    let programdata = rpc_client
+        .get_account(&programdata_address)
+        .await
+        .map_err(|e| Error::RpcError(e))?;
+    Ok((programdata_address, programdata))
+}
+
+pub async fn inspect_program(
+    rpc_client: &RpcClient,
+    program_address: &Pubkey,
+) -> Result<ProgramInfo, Error> {
+    let (programdata_address, programdata) = fetch_programdata(rpc_client, program_address).await?;
+
+    let (last_deployed_slot, upgrade_authority) =
+        match bincode::deserialize::<UpgradeableLoaderState>(&programdata.data) {
+            Ok(UpgradeableLoaderState::ProgramData {
+                slot,
+                upgrade_authority_address,
+            }) => (slot, upgrade_authority_address),
+            _ => return Err(Error::InvalidProgramAccount),
+        };
+
+    let header_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    let allocated = programdata
+        .data
+        .get(header_len..)
+        .ok_or(Error::InvalidProgramAccount)?;
+    // the same bytes verification hashes, a matching .so has the same sha-256
+    let executable = deployed_executable(allocated);
+
+    Ok(ProgramInfo {
+        program_address: program_address.to_string(),
+        programdata_address: programdata_address.to_string(),
+        upgrade_authority: upgrade_authority.map(|authority| authority.to_string()),
+        last_deployed_slot,
+        data_len: executable.len(),
+        allocated_len: allocated.len(),
+        rent_balance: programdata.lamports,
+        sha256: sha256_hex(executable),
+    })
+}
+
+// the executable behind a program, programdata or buffer account, with the zero padding kept
+// (the same bytes `solana program dump` writes), an elf usually ends in zeros of its own
+pub async fn fetch_executable(rpc_client: &RpcClient, address: &Pubkey) -> Result<Vec<u8>, Error> {
+    let account = rpc_client
+        .get_account(address)
+        .await
+        .map_err(|e| Error::RpcError(e))?;
+
+    if account.owner != bpf_loader_upgradeable::id() {
+        return Err(Error::InvalidProgramAccount);
+    }
+    let header_len = match bincode::deserialize::<UpgradeableLoaderState>(&account.data) {
+        Ok(UpgradeableLoaderState::Program { .. }) => {
+            return fetch_program_executable(rpc_client, address).await
+        }
+        Ok(UpgradeableLoaderState::ProgramData { .. }) => {
+            UpgradeableLoaderState::size_of_programdata_metadata()
+        }
+        Ok(UpgradeableLoaderState::Buffer { .. }) => {
+            UpgradeableLoaderState::size_of_buffer_metadata()
+        }
+        _ => return Err(Error::InvalidProgramAccount),
+    };
+
+    match account.data.get(header_len..) {
+        Some(executable) if !trim_zero_padding(executable).is_empty() => Ok(executable.to_vec()),
+        // nothing was written to it yet
+        _ => Err(Error::InvalidProgramAccount),
+    }
+}
@@ -1,18 +1,37 @@
-use bincode::serialized_size;
 use solana_sdk::{
     bpf_loader_upgradeable::{
-        create_buffer, deploy_with_max_program_len, set_buffer_authority, upgrade, write,
+        close, create_buffer, deploy_with_max_program_len, extend_program, set_buffer_authority,
+        set_upgrade_authority, set_upgrade_authority_checked, upgrade, write,
     },
     compute_budget::ComputeBudgetInstruction,
     hash::Hash,
     instruction::{Instruction, InstructionError},
+    message::Message,
     pubkey::Pubkey,
     signature::Keypair,
     signer::Signer,
+    system_instruction::{advance_nonce_account, create_nonce_account},
     transaction::Transaction,
 };
 
-use crate::{programs::get_vec_with_batched_data, settings::LSettings};
+use crate::deployer::get_vec_with_batched_data;
+use crate::nonce::NonceInfo;
+
+pub const DEFAULT_UNIT_LIMIT: u32 = 25000;
+pub const DEFAULT_UNIT_PRICE: u64 = 550_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriorityFees {
+    pub unit_limit: u32,
+    pub unit_price: u64,
+}
+
+// the keys the buffer writes and the deploy are signed with, the payer can be the authority
+#[derive(Clone, Copy)]
+pub struct LoaderSigners<'a> {
+    pub payer: &'a Keypair,
+    pub authority: &'a Keypair,
+}
 
 pub fn get_priority_fees_ixs(unit_limit: u32, unit_price: u64) -> [Instruction; 2] {
     let comput_unit_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(unit_limit);
@@ -20,26 +39,38 @@ pub fn get_priority_fees_ixs(unit_limit: u32, unit_price: u64) -> [Instruction;
     [comput_unit_limit_ix, comput_unit_price_ix]
 }
 
+// prepends the compute budget instructions, every transaction the app builds goes through this
+pub fn with_priority_fees_ixs(
+    ixs: Vec<Instruction>,
+    priority_fees: &PriorityFees,
+) -> Vec<Instruction> {
+    let mut priority_ixs =
+        get_priority_fees_ixs(priority_fees.unit_limit, priority_fees.unit_price).to_vec();
+    priority_ixs.extend(ixs);
+    priority_ixs
+}
+
+// the payer funds the buffer, the authority only has to be named as its buffer authority
 pub fn create_buffer_account(
     buffer_account: &Keypair,
-    authority: &Keypair,
+    payer: &Keypair,
+    authority: &Pubkey,
     lamports: u64,
     program_bytes: &Vec<u8>,
     recent_blockhash: Hash,
-    settings: &LSettings,
+    priority_fees: &PriorityFees,
 ) -> Result<Transaction, InstructionError> {
-    println!("lamports: {}, bytes: {:?}", lamports, program_bytes.len());
     let mut create_buffer_ix = create_buffer(
-        &authority.pubkey(),
+        &payer.pubkey(),
         &buffer_account.pubkey(),
-        &authority.pubkey(),
+        authority,
         lamports,
         program_bytes.len(),
     )?;
-    let priority_ixs = get_priority_fees_ixs(settings.unit_limit, settings.unit_price);
+    let priority_ixs = get_priority_fees_ixs(priority_fees.unit_limit, priority_fees.unit_price);
     create_buffer_ix.splice(0..0, priority_ixs);
-    let mut tx = Transaction::new_with_payer(&create_buffer_ix, Some(&authority.pubkey()));
-    tx.sign(&[&authority, &buffer_account], recent_blockhash);
+    let mut tx = Transaction::new_with_payer(&create_buffer_ix, Some(&payer.pubkey()));
+    tx.sign(&[&payer, &buffer_account], recent_blockhash);
 
     Ok(tx)
 }
@@ -47,90 +78,251 @@ pub fn create_buffer_account(
 pub fn write_data(
     buffer_address: &Pubkey,
     program_bytes: &Vec<u8>,
-    authority: &Keypair,
+    signers: LoaderSigners,
     recent_blockhash: Hash,
     bytes_per_chunk: usize,
-    settings: &LSettings,
+    pending_chunks: &[usize],
+    priority_fees: &PriorityFees,
 ) -> Vec<Transaction> {
     let mut transactions = Vec::new();
     let write_data_batches = get_vec_with_batched_data(bytes_per_chunk, program_bytes);
-    let priority_ixs = get_priority_fees_ixs(settings.unit_limit, settings.unit_price);
+    let priority_ixs = get_priority_fees_ixs(priority_fees.unit_limit, priority_fees.unit_price);
     for (index, data) in write_data_batches.into_iter().enumerate() {
+        // chunks already present on the buffer are skipped
+        if !pending_chunks.contains(&index) {
+            continue;
+        }
         let mut ixs = Vec::new();
         let write_ix = write(
             &buffer_address,
-            &authority.pubkey(),
+            &signers.authority.pubkey(),
             index as u32 * bytes_per_chunk as u32,
             data,
         );
 
         ixs.extend_from_slice(&priority_ixs);
-        ixs.push(write_ix.clone());
-        let mut tx = Transaction::new_with_payer(&ixs, Some(&authority.pubkey()));
-        tx.sign(&[&authority], recent_blockhash);
-        let size =serialized_size(&tx).unwrap() as usize;
-        let ix_write_size =serialized_size(&write_ix).unwrap() as usize;
-        println!("tx size: {}, tx_chunk: {}, write_ix_size: {}", size, bytes_per_chunk, ix_write_size);
+        ixs.push(write_ix);
+        let mut tx = Transaction::new_with_payer(&ixs, Some(&signers.payer.pubkey()));
+        tx.sign(&[signers.payer, signers.authority], recent_blockhash);
         transactions.push(tx)
     }
     transactions
 }
 
-// TODO: implements set a new buffer authority
-// this will be useful if you want to use your squad authority to upgrade a program using this buffer account
+// hands the buffer to another authority, like a squads vault that then proposes the upgrade
+// with the message from `upgrade_program_message`
 pub fn set_new_buffer_auth(
     buffer_address: &Pubkey,
+    payer: &Keypair,
     authority: &Keypair,
     recent_blockhash: Hash,
     new_authority: &Pubkey,
+    priority_fees: &PriorityFees,
 ) -> Transaction {
     let set_new_auth_ix = set_buffer_authority(buffer_address, &authority.pubkey(), new_authority);
-    let mut tx = Transaction::new_with_payer(&[set_new_auth_ix], Some(&authority.pubkey()));
-    tx.sign(&[&authority], recent_blockhash);
+    let ixs = with_priority_fees_ixs(vec![set_new_auth_ix], priority_fees);
+    let mut tx = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+    tx.sign(&[&payer, &authority], recent_blockhash);
     tx
 }
 
-pub fn deploy_program(
+// None as the new authority makes the program immutable
+pub fn set_program_upgrade_authority(
+    program_address: &Pubkey,
+    payer: &Keypair,
+    authority: &Keypair,
+    new_authority: Option<&Pubkey>,
+    recent_blockhash: Hash,
+    priority_fees: &PriorityFees,
+) -> Transaction {
+    let set_authority_ix =
+        set_upgrade_authority(program_address, &authority.pubkey(), new_authority);
+    let ixs = with_priority_fees_ixs(vec![set_authority_ix], priority_fees);
+    let mut tx = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+    tx.sign(&[&payer, &authority], recent_blockhash);
+    tx
+}
+
+// the new authority signs too, so the program can't be handed to a key nobody controls
+pub fn set_program_upgrade_authority_checked(
+    program_address: &Pubkey,
+    payer: &Keypair,
     authority: &Keypair,
+    new_authority: &Keypair,
+    recent_blockhash: Hash,
+    priority_fees: &PriorityFees,
+) -> Transaction {
+    let set_authority_ix = set_upgrade_authority_checked(
+        program_address,
+        &authority.pubkey(),
+        &new_authority.pubkey(),
+    );
+    let ixs = with_priority_fees_ixs(vec![set_authority_ix], priority_fees);
+    let mut tx = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+    tx.sign(&[&payer, &authority, &new_authority], recent_blockhash);
+    tx
+}
+
+// the payer funds the program account, the authority becomes the upgrade authority
+pub fn deploy_program(
+    signers: LoaderSigners,
     program_keypair: &Keypair,
     buffer_address: &Pubkey,
-    program_bytes: &Vec<u8>,
+    max_program_len: usize,
     program_lamports: u64,
     recent_blockhash: Hash,
+    priority_fees: &PriorityFees,
 ) -> Result<Transaction, InstructionError> {
-    let payer_address = &authority.pubkey();
+    let payer_address = &signers.payer.pubkey();
     let program_address = &program_keypair.pubkey();
-    let len = program_bytes.len();
 
     let deploy_program = deploy_with_max_program_len(
         payer_address,
         program_address,
         buffer_address,
-        payer_address,
+        &signers.authority.pubkey(),
         program_lamports,
-        len,
+        max_program_len,
     )?;
 
-    let mut tx = Transaction::new_with_payer(&deploy_program, Some(&authority.pubkey()));
-    tx.sign(&[&authority, &program_keypair], recent_blockhash);
+    let ixs = with_priority_fees_ixs(deploy_program, priority_fees);
+    let mut tx = Transaction::new_with_payer(&ixs, Some(payer_address));
+    tx.sign(
+        &[signers.payer, signers.authority, program_keypair],
+        recent_blockhash,
+    );
     Ok(tx)
 }
 
+// the buffer lamports are sent to the spill account once the upgrade consumes it
 pub fn upgrade_program(
-    program_keypair: &Keypair,
+    program_address: &Pubkey,
     buffer_address: &Pubkey,
+    payer: &Keypair,
     authority: &Keypair,
+    spill_address: &Pubkey,
     recent_blockhash: Hash,
+    priority_fees: &PriorityFees,
 ) -> Transaction {
-    let authority_pubkey = &authority.pubkey();
-    let program_address = &program_keypair.pubkey();
     let upgrade_program_ix = upgrade(
         program_address,
         buffer_address,
-        authority_pubkey,
-        authority_pubkey,
+        &authority.pubkey(),
+        spill_address,
+    );
+    let ixs = with_priority_fees_ixs(vec![upgrade_program_ix], priority_fees);
+    let mut tx = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+    tx.sign(&[&payer, &authority], recent_blockhash);
+    tx
+}
+
+// unsigned upgrade for an authority that signs elsewhere, like a multisig vault, which
+// also pays for it. No compute budget instructions, the multisig executes it as an inner call
+pub fn upgrade_program_message(
+    program_address: &Pubkey,
+    buffer_address: &Pubkey,
+    authority: &Pubkey,
+    spill_address: &Pubkey,
+    recent_blockhash: Hash,
+) -> Message {
+    let upgrade_program_ix = upgrade(program_address, buffer_address, authority, spill_address);
+    Message::new_with_blockhash(&[upgrade_program_ix], Some(authority), &recent_blockhash)
+}
+
+// unsigned message that uses a durable nonce instead of a recent blockhash, the nonce
+// advance goes first and the nonce authority has to sign with the payer
+pub fn nonce_message(
+    ixs: Vec<Instruction>,
+    payer: &Pubkey,
+    nonce: &NonceInfo,
+    priority_fees: &PriorityFees,
+) -> Message {
+    let ixs = with_priority_fees_ixs(ixs, priority_fees);
+    let mut message = Message::new_with_nonce(ixs, Some(payer), &nonce.address, &nonce.authority);
+    message.recent_blockhash = nonce.blockhash;
+    message
+}
+
+// signed right away, for signers that are all loaded. The nonce authority must be one of them
+pub fn nonce_transaction(
+    ixs: Vec<Instruction>,
+    payer: &Keypair,
+    other_signers: &[&Keypair],
+    nonce: &NonceInfo,
+    priority_fees: &PriorityFees,
+) -> Transaction {
+    let message = nonce_message(ixs, &payer.pubkey(), nonce, priority_fees);
+    let mut signers = vec![payer];
+    signers.extend_from_slice(other_signers);
+    let mut tx = Transaction::new_unsigned(message);
+    tx.sign(&signers, nonce.blockhash);
+    tx
+}
+
+// the payer funds the nonce account, the authority doesn't have to sign to be set as its
+// nonce authority
+pub fn create_nonce(
+    nonce_account: &Keypair,
+    payer: &Keypair,
+    authority: &Pubkey,
+    lamports: u64,
+    recent_blockhash: Hash,
+    priority_fees: &PriorityFees,
+) -> Transaction {
+    let create_nonce_ixs = create_nonce_account(
+        &payer.pubkey(),
+        &nonce_account.pubkey(),
+        authority,
+        lamports,
     );
-    let mut tx = Transaction::new_with_payer(&[upgrade_program_ix], Some(&authority.pubkey()));
-    tx.sign(&[&authority], recent_blockhash);
+    let ixs = with_priority_fees_ixs(create_nonce_ixs, priority_fees);
+    let mut tx = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+    tx.sign(&[&payer, &nonce_account], recent_blockhash);
+    tx
+}
+
+// a new nonce invalidates every transaction signed with the previous one
+pub fn advance_nonce(
+    nonce_address: &Pubkey,
+    payer: &Keypair,
+    nonce_authority: &Keypair,
+    recent_blockhash: Hash,
+    priority_fees: &PriorityFees,
+) -> Transaction {
+    let advance_nonce_ix = advance_nonce_account(nonce_address, &nonce_authority.pubkey());
+    let ixs = with_priority_fees_ixs(vec![advance_nonce_ix], priority_fees);
+    let mut tx = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+    tx.sign(&[&payer, &nonce_authority], recent_blockhash);
+    tx
+}
+
+// grows the programdata account so a bigger binary fits, the payer covers the extra rent
+pub fn extend_program_data(
+    program_address: &Pubkey,
+    payer: &Keypair,
+    additional_bytes: u32,
+    recent_blockhash: Hash,
+    priority_fees: &PriorityFees,
+) -> Transaction {
+    let extend_program_ix = extend_program(program_address, Some(&payer.pubkey()), additional_bytes);
+    let ixs = with_priority_fees_ixs(vec![extend_program_ix], priority_fees);
+    let mut tx = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+    tx.sign(&[&payer], recent_blockhash);
+    tx
+}
+
+// closes a buffer account, the remaining lamports are sent to the recipient
+pub fn close_buffer(
+    buffer_address: &Pubkey,
+    recipient: &Pubkey,
+    payer: &Keypair,
+    authority: &Keypair,
+    recent_blockhash: Hash,
+    priority_fees: &PriorityFees,
+) -> Transaction {
+    let close_buffer_ix = close(buffer_address, recipient, &authority.pubkey());
+    let ixs = with_priority_fees_ixs(vec![close_buffer_ix], priority_fees);
+    let mut tx = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+    tx.sign(&[&payer, &authority], recent_blockhash);
     tx
 }
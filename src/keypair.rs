@@ -1,7 +1,34 @@
-use std::path::PathBuf;
-use solana_sdk::signature::{read_keypair_file, Keypair};
+use bip39::{Language, Mnemonic, MnemonicType};
+use solana_sdk::{
+    signature::{read_keypair_file, Keypair},
+    signer::{
+        keypair::{keypair_from_seed_phrase_and_passphrase, write_keypair_file},
+        Signer,
+    },
+};
+use std::{fs, path::PathBuf};
 
-pub fn load_keypair_from_file(path: PathBuf) -> Keypair {
-    let keypair = read_keypair_file(path).unwrap_or(Keypair::new());
-    keypair
+use crate::{errors::Error, files::buffers_dir};
+
+pub fn load_keypair_from_file(path: PathBuf) -> Result<Keypair, Error> {
+    read_keypair_file(path).map_err(|_| Error::InvalidKeypairFile)
+}
+
+// same approach as `solana program deploy`, the buffer keypair is derived from a
+// 12 words seed phrase so it can be recovered with `solana-keygen recover`
+pub fn new_buffer_keypair() -> (Keypair, String) {
+    let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
+    let keypair = keypair_from_seed_phrase_and_passphrase(mnemonic.phrase(), "")
+        .expect("A valid seed phrase is expected");
+    (keypair, mnemonic.into_phrase())
+}
+
+// writes the buffer keypair to the buffers dir, named after its pubkey
+pub fn save_buffer_keypair(buffer_account: &Keypair) -> Result<PathBuf, Error> {
+    let dir = buffers_dir();
+    fs::create_dir_all(&dir).map_err(|_| Error::KeypairPersistError)?;
+    let mut path = dir;
+    path.push(format!("{}.json", buffer_account.pubkey()));
+    write_keypair_file(buffer_account, &path).map_err(|_| Error::KeypairPersistError)?;
+    Ok(path)
 }
@@ -0,0 +1,19 @@
+// deploy engine shared by the gui and the command line mode, it doesn't depend on iced
+// so it can be embedded in other tooling with `default-features = false`
+pub mod app_settings;
+pub mod buffers;
+pub mod clusters;
+pub mod cost;
+pub mod deployer;
+pub mod errors;
+pub mod fees;
+pub mod files;
+pub mod inspect;
+pub mod instructions;
+pub mod keypair;
+pub mod multisig;
+pub mod nonce;
+pub mod offline;
+pub mod solana_config;
+pub mod transactions;
+pub mod verify;
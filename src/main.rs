@@ -1,30 +1,53 @@
+use buffer_panels::LBuffers;
 use components::error;
 use iced::{
     clipboard,
-    widget::{column, container},
+    widget::{column, container, scrollable},
     Element, Subscription, Task, Theme,
 };
-use programs::{get_program_bytes, LPrograms, Progress};
+use lich_deployer::buffers::{load_local_buffers, load_owned_buffers, LocalBuffer, OwnedBuffer};
+use lich_deployer::cost::CostEstimate;
+use lich_deployer::deployer::{buffer_lamports, get_program_bytes, Progress};
+use lich_deployer::inspect::ProgramInfo;
+use lich_deployer::nonce::NonceInfo;
+use lich_deployer::offline::OfflineTransaction;
+use lich_deployer::verify::ProgramVerification;
+use programs::{LPrograms, OfflineKind};
 use settings::{keypair_balance, LSettings};
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::signature::Signature;
-use std::sync::Arc;
-use std::{path::PathBuf, time::Duration};
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::Signer,
+};
+use std::{env, path::PathBuf, process, str::FromStr, time::Duration};
 use tokio::time;
+mod buffer_panels;
+mod cli;
 mod components;
-mod errors;
-mod files;
-mod instructions;
-mod keypair;
+mod dialogs;
 mod programs;
 mod settings;
-mod transactions;
 
-use errors::Error;
-use files::{default_keypair_path, pick_file, FileType};
-use keypair::load_keypair_from_file;
+use dialogs::{pick_file, FileType};
+use lich_deployer::app_settings::{
+    load_app_settings, reset_app_settings, save_app_settings, AppSettings,
+};
+use lich_deployer::clusters::{is_mainnet, save_cluster_profiles, ClusterProfile};
+use lich_deployer::errors::Error;
+use lich_deployer::fees::{UnitLimitMode, UnitPriceMode};
+use lich_deployer::files::default_keypair_path;
+use lich_deployer::keypair::load_keypair_from_file;
+use lich_deployer::multisig::{squads_vault_address, UpgradeProposal, DEFAULT_VAULT_INDEX};
+use lich_deployer::solana_config::{load_solana_config, save_solana_config};
 
 fn main() -> iced::Result {
+    // any argument switches to the headless command line mode
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        process::exit(cli::run(args));
+    }
+
     iced::application(Lich::title, Lich::update, Lich::view)
         .theme(Lich::theme)
         .subscription(Lich::subscription)
@@ -34,6 +57,7 @@ fn main() -> iced::Result {
 struct Lich {
     pub settings: LSettings,
     pub programs: LPrograms,
+    pub buffers: LBuffers,
     pub error: Option<Error>,
 }
 
@@ -42,6 +66,7 @@ impl Default for Lich {
         Self {
             settings: LSettings::default(),
             programs: LPrograms::default(),
+            buffers: LBuffers::default(),
             error: None,
         }
     }
@@ -54,54 +79,239 @@ enum Message {
     LoadProgramAccount(Result<PathBuf, Error>),
     LoadProgramAuthority(Result<PathBuf, Error>),
     AuthoritySolBalance(Result<u64, Error>),
+    PickFeePayer,
+    LoadFeePayer(Result<PathBuf, Error>),
+    ClearFeePayer,
+    RefundRecipientInput(String),
+    FeePayerSolBalance(Result<u64, Error>),
     PickProgram,
     LoadProgram(Result<PathBuf, Error>),
     WriteData,
     RpcClient(String),
     ComputeUnitPrice(String),
     ComputeUniteLimit(String),
+    UnitPriceMode(UnitPriceMode),
+    UnitLimitMode(UnitLimitMode),
     UpdateProgress(Result<Progress, Error>),
     CopyToCliboard(String),
     ErrorCleared,
     DeployProgram,
     SignatureToDisplay(Result<Signature, Error>),
     SetNewBufferAuth,
-    SetNewBufferAuthInput(String)
+    SetNewBufferAuthInput(String),
+    PickBufferAccount,
+    LoadBufferAccount(Result<PathBuf, Error>),
+    ResumeBufferInput(String),
+    RefreshLocalBuffers,
+    LocalBuffers(Result<Vec<LocalBuffer>, Error>),
+    CloseBuffer,
+    CloseBufferTargetInput(String),
+    CloseRecipientInput(String),
+    CloseBufferLamports(Result<u64, Error>),
+    BufferClosed(Result<Signature, Error>),
+    RefreshOwnedBuffers,
+    OwnedBuffers(Result<Vec<OwnedBuffer>, Error>),
+    SelectBuffer(Pubkey, bool),
+    SelectBufferAuthorityTarget(Pubkey),
+    PickSolanaConfig,
+    LoadSolanaConfig(Result<PathBuf, Error>),
+    SaveSolanaConfig,
+    SolanaConfigSaved(Result<(), Error>),
+    WebsocketUrl(String),
+    Commitment(CommitmentLevel),
+    RpcUrlInput(String),
+    SelectClusterProfile(ClusterProfile),
+    ProfileNameInput(String),
+    SaveClusterProfile,
+    ClusterProfilesSaved(Result<(), Error>),
+    // the rpc url it was checked for, the answer is dropped if the url changed since
+    ClusterDetected(String, bool),
+    AppSettingsSaved(Result<(), Error>),
+    ResetSettings,
+    SettingsReset(Result<(), Error>),
+    EstimateCost,
+    MaxProgramLenInput(String),
+    AutoExtend(bool),
+    ProgramAddressInput(String),
+    NewUpgradeAuthorityInput(String),
+    PickNewUpgradeAuthority,
+    LoadNewUpgradeAuthority(Result<PathBuf, Error>),
+    SetUpgradeAuthority,
+    ImmutableConfirmationInput(String),
+    MakeImmutable,
+    MultisigAddressInput(String),
+    VaultAddressInput(String),
+    SpillAddressInput(String),
+    ExportUpgradeProposal,
+    UpgradeProposalExported(Result<UpgradeProposal, Error>),
+    OfflineAuthorityInput(String),
+    NonceAddressInput(String),
+    CreateNonceAccount,
+    NonceAccountCreated(Result<(Pubkey, Signature), Error>),
+    RefreshNonce,
+    NonceInfoLoaded(Result<NonceInfo, Error>),
+    AdvanceNonce,
+    UseNonce(bool),
+    ExportOfflineTransaction(OfflineKind),
+    OfflineTransactionBuilt(Result<OfflineTransaction, Error>),
+    PresignerInput(String),
+    AddPresigner,
+    SubmitOfflineTransaction,
+    CostEstimated(Result<CostEstimate, Error>),
+    ProgramDeployed(Result<Signature, Error>),
+    VerifyProgram,
+    ProgramVerified(Result<ProgramVerification, Error>),
+    InspectProgramInput(String),
+    InspectProgram,
+    ProgramInspected(Result<ProgramInfo, Error>),
+    DumpProgram,
+    ProgramDumped(Result<PathBuf, Error>),
 }
 
 impl Lich {
     fn new() -> (Self, Task<Message>) {
-        (
-            Lich::default(),
-            Task::perform(
-                async { Ok(default_keypair_path()) },
-                Message::LoadProgramAuthority,
+        let mut lich = Lich::default();
+        let saved_settings = load_app_settings();
+        // a saved keypair that can't be read anymore is reported once the app is up
+        let keypair_error = match &saved_settings {
+            Some(app_settings) => lich.settings.apply_app_settings(app_settings).err(),
+            None => None,
+        };
+
+        // the saved paths win over the ones of the active cluster profile
+        let (program_path, program_keypair_path) = match saved_settings {
+            Some(app_settings) => (app_settings.program_path, app_settings.program_keypair_path),
+            None => (
+                None,
+                lich.settings
+                    .cluster_profiles
+                    .active_profile()
+                    .and_then(|profile| profile.program_keypair_path.clone()),
             ),
-        )
+        };
+
+        let mut tasks = vec![
+            lich.reload_authority(),
+            lich.detect_cluster(),
+            Task::done(Message::RefreshLocalBuffers),
+        ];
+        if let Some(err) = keypair_error {
+            tasks.push(Task::done(Message::LoadProgramAuthority(Err(err))));
+        }
+        // files that were moved or deleted since the last session are skipped, a missing
+        // program keypair would otherwise be replaced by a random one
+        if let Some(path) = program_path.filter(|path| path.is_file()) {
+            tasks.push(Task::done(Message::LoadProgram(Ok(path))));
+        }
+        if let Some(path) = program_keypair_path.filter(|path| path.is_file()) {
+            tasks.push(Task::done(Message::LoadProgramAccount(Ok(path))));
+        }
+        (lich, Task::batch(tasks))
     }
 
+    // every change to the persisted settings is written to disk right away
     fn update(&mut self, message: Message) -> Task<Message> {
+        // a reset already removed the file, it shouldn't be written again with the defaults
+        let is_reset = matches!(message, Message::SettingsReset(Ok(())));
+        let previous_settings = self.app_settings();
+        let task = self.handle_message(message);
+        let app_settings = self.app_settings();
+        if is_reset || app_settings == previous_settings {
+            return task;
+        }
+        Task::batch([
+            task,
+            Task::perform(
+                async move { save_app_settings(&app_settings) },
+                Message::AppSettingsSaved,
+            ),
+        ])
+    }
+
+    fn app_settings(&self) -> AppSettings {
+        AppSettings {
+            rpc_url: self.settings.rpc_client.url(),
+            websocket_url: self.settings.websocket_url.clone(),
+            commitment: self.settings.commitment.commitment,
+            unit_limit: self.settings.unit_limit,
+            unit_price: self.settings.unit_price,
+            unit_price_mode: self.settings.unit_price_mode,
+            unit_limit_mode: self.settings.unit_limit_mode,
+            max_program_len: self.settings.max_program_len(),
+            auto_extend: self.settings.auto_extend,
+            keypair_path: self.settings.keypair_path.clone(),
+            fee_payer_path: self.settings.fee_payer_path.clone(),
+            refund_recipient: self.settings.refund_recipient_input.trim().to_string(),
+            program_path: self.settings.program_path.clone(),
+            program_keypair_path: self.programs.program_account_path.clone(),
+            solana_config_path: self.settings.config_path.clone(),
+        }
+    }
+
+    fn handle_message(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::PickProgramAuthority => {
                 Task::perform(pick_file(FileType::Keypair), Message::LoadProgramAuthority)
             }
             Message::LoadProgramAuthority(Ok(path)) => {
-                self.settings.keypair_path = Some(path.to_path_buf());
-                self.settings.keypair = load_keypair_from_file(path.to_path_buf()).into();
-                Task::perform(
-                    keypair_balance(path, self.settings.rpc_client.clone()),
-                    Message::AuthoritySolBalance,
-                )
+                if let Err(err) = self.settings.set_keypair(path.to_path_buf()) {
+                    return Task::done(Message::LoadProgramAuthority(Err(err)));
+                }
+                Task::batch([
+                    Task::perform(
+                        keypair_balance(path, self.settings.rpc_client.clone()),
+                        Message::AuthoritySolBalance,
+                    ),
+                    Task::done(Message::RefreshOwnedBuffers),
+                ])
             }
             Message::LoadProgramAuthority(Err(err)) => {
                 self.error = Some(err);
                 Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
             }
+            Message::PickFeePayer => {
+                Task::perform(pick_file(FileType::Keypair), Message::LoadFeePayer)
+            }
+            Message::LoadFeePayer(Ok(path)) => {
+                if let Err(err) = self.settings.set_fee_payer(path) {
+                    return Task::done(Message::LoadFeePayer(Err(err)));
+                }
+                Task::batch([
+                    self.refresh_fee_payer_balance(),
+                    Task::done(Message::EstimateCost),
+                ])
+            }
+            Message::LoadFeePayer(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::ClearFeePayer => {
+                self.settings.clear_fee_payer();
+                Task::done(Message::EstimateCost)
+            }
+            Message::RefundRecipientInput(refund_recipient) => {
+                self.settings.refund_recipient_input = refund_recipient;
+                Task::none()
+            }
+            Message::FeePayerSolBalance(Ok(balance)) => {
+                self.settings.fee_payer_balance = Some(balance);
+                Task::none()
+            }
+            Message::FeePayerSolBalance(Err(e)) => {
+                self.error = Some(e);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
             Message::PickProgramAccount => {
                 Task::perform(pick_file(FileType::Keypair), Message::LoadProgramAccount)
             }
             Message::LoadProgramAccount(Ok(path_buf)) => {
-                self.programs.program_account = Some(load_keypair_from_file(path_buf).into());
+                let program_account = match load_keypair_from_file(path_buf.to_path_buf()) {
+                    Ok(program_account) => program_account,
+                    Err(err) => return Task::done(Message::LoadProgramAccount(Err(err))),
+                };
+                self.programs.program_account_path = Some(path_buf);
+                self.programs.program_address_input = None;
+                self.programs.program_account = Some(program_account.into());
                 Task::none()
             }
             Message::LoadProgramAccount(Err(err)) => {
@@ -127,7 +337,14 @@ impl Lich {
                         get_program_bytes(path.to_str().expect("A valid path is expected"))
                             .unwrap_or(Vec::new())
                 }
-                Task::none()
+                // the owned buffers were matched against the previous program until they reload
+                for buffer in self.buffers.owned.iter_mut() {
+                    buffer.matches_program = false;
+                }
+                Task::batch([
+                    Task::done(Message::EstimateCost),
+                    Task::done(Message::RefreshOwnedBuffers),
+                ])
             }
             Message::LoadProgram(Err(err)) => {
                 self.error = Some(err);
@@ -137,39 +354,65 @@ impl Lich {
                 self.programs.signature = None;
                 self.programs.is_writing_data = true;
                 self.programs.is_data_writed = false;
+                self.programs.is_verifying = false;
+                self.programs.mismatched_ranges = Vec::new();
                 self.programs.transactions = (0, 0);
+                self.programs.buffer_keypair_path = None;
+                self.programs.retry_unit_price = None;
                 Task::none()
             }
             Message::UpdateProgress(progress) => {
                 match progress {
                     Ok(Progress::Sending { sent, total }) => {
+                        self.programs.is_verifying = false;
                         self.programs.transactions = (sent, total);
                     }
-                    Ok(Progress::Completed { buffer_account }) => {
-                        println!("Data written!");
+                    Ok(Progress::BufferKeypairSaved { path }) => {
+                        self.programs.buffer_keypair_path = Some(path);
+                    }
+                    Ok(Progress::Retrying { unit_price }) => {
+                        self.programs.retry_unit_price = Some(unit_price);
+                    }
+                    Ok(Progress::Verifying) => {
+                        self.programs.is_verifying = true;
+                    }
+                    Ok(Progress::Rewriting { ranges }) => {
+                        self.programs.is_verifying = false;
+                        self.programs.mismatched_ranges = ranges;
+                    }
+                    Ok(Progress::Completed { .. }) => {
+                        self.programs.retry_unit_price = None;
+                        self.programs.is_verifying = false;
+                        self.programs.mismatched_ranges = Vec::new();
                         self.programs.transactions = (0, 0);
-                        self.programs.buffer_account = buffer_account;
                         self.programs.is_data_writed = true;
                         self.programs.is_writing_data = false;
-                        return Task::perform(
-                            keypair_balance(
-                                self.settings
-                                    .keypair_path
-                                    .clone()
-                                    .unwrap_or(default_keypair_path()),
-                                self.settings.rpc_client.clone(),
+                        return Task::batch([
+                            Task::perform(
+                                keypair_balance(
+                                    self.settings
+                                        .keypair_path
+                                        .clone()
+                                        .unwrap_or(default_keypair_path()),
+                                    self.settings.rpc_client.clone(),
+                                ),
+                                Message::AuthoritySolBalance,
                             ),
-                            Message::AuthoritySolBalance,
-                        );
+                            self.refresh_fee_payer_balance(),
+                            self.refresh_buffers(),
+                            self.fetch_close_lamports(),
+                        ]);
                     }
                     Ok(Progress::Idle) => {
-                        println!("Starting")
+                        self.programs.retry_unit_price = None;
                     }
                     Err(e) => {
                         self.error = Some(e);
                         self.programs.transactions = (0, 0);
                         self.programs.is_data_writed = false;
                         self.programs.is_writing_data = false;
+                        self.programs.is_verifying = false;
+                        self.programs.retry_unit_price = None;
                         return Task::perform(Lich::sleep(), |_| Message::ErrorCleared);
                     }
                 }
@@ -177,28 +420,93 @@ impl Lich {
             }
             Message::DeployProgram => Task::perform(
                 LPrograms::deploy_or_upgrade(self.programs.clone(), self.settings.clone()),
-                Message::SignatureToDisplay,
+                Message::ProgramDeployed,
             ),
+            // the deployed executable is compared with the .so right after it lands
+            Message::ProgramDeployed(Ok(signature)) => Task::batch([
+                Task::done(Message::SignatureToDisplay(Ok(signature))),
+                Task::done(Message::VerifyProgram),
+            ]),
+            Message::ProgramDeployed(Err(err)) => Task::done(Message::SignatureToDisplay(Err(err))),
+            Message::VerifyProgram => {
+                self.programs.verification = None;
+                Task::perform(
+                    LPrograms::verify_program(self.programs.clone(), self.settings.clone()),
+                    Message::ProgramVerified,
+                )
+            }
+            Message::ProgramVerified(Ok(verification)) => {
+                self.programs.verification = Some(verification);
+                Task::none()
+            }
+            Message::ProgramVerified(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::InspectProgramInput(program_address) => {
+                self.programs.inspect_input = Some(program_address);
+                Task::none()
+            }
+            Message::InspectProgram => {
+                self.programs.program_info = None;
+                Task::perform(
+                    LPrograms::inspect_program(self.programs.clone(), self.settings.clone()),
+                    Message::ProgramInspected,
+                )
+            }
+            Message::ProgramInspected(Ok(program_info)) => {
+                self.programs.program_info = Some(program_info);
+                Task::none()
+            }
+            Message::ProgramInspected(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::DumpProgram => {
+                self.programs.dump_path = None;
+                Task::perform(
+                    LPrograms::dump_program(self.programs.clone(), self.settings.clone()),
+                    Message::ProgramDumped,
+                )
+            }
+            Message::ProgramDumped(Ok(path)) => {
+                self.programs.dump_path = Some(path);
+                Task::none()
+            }
+            Message::ProgramDumped(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
             Message::SignatureToDisplay(Ok(signature)) => {
                 self.programs.signature = Some(signature);
-                Task::perform(
-                    keypair_balance(
-                        self.settings
-                            .keypair_path
-                            .clone()
-                            .unwrap_or(default_keypair_path()),
-                        self.settings.rpc_client.clone(),
+                // a deploy on the nonce or an advance leaves a new nonce behind
+                Task::batch([
+                    Task::done(Message::RefreshNonce),
+                    Task::perform(
+                        keypair_balance(
+                            self.settings
+                                .keypair_path
+                                .clone()
+                                .unwrap_or(default_keypair_path()),
+                            self.settings.rpc_client.clone(),
+                        ),
+                        Message::AuthoritySolBalance,
                     ),
-                    Message::AuthoritySolBalance,
-                )
+                    self.refresh_fee_payer_balance(),
+                    self.refresh_buffers(),
+                ])
             }
             Message::SignatureToDisplay(Err(err)) => {
                 self.error = Some(err);
                 Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
             }
-            Message::RpcClient(rpc_client) => {
-                self.settings.rpc_client = Arc::new(RpcClient::new(rpc_client));
-                Task::none()
+            Message::RpcClient(rpc_url) => {
+                self.settings.set_rpc_url(rpc_url);
+                Task::batch([
+                    self.reload_authority(),
+                    self.detect_cluster(),
+                    Task::done(Message::RefreshLocalBuffers),
+                ])
             }
             Message::CopyToCliboard(value_to_copy) => clipboard::write(value_to_copy.to_string()),
             Message::ErrorCleared => {
@@ -213,6 +521,14 @@ impl Lich {
                 };
                 Task::none()
             }
+            Message::UnitPriceMode(unit_price_mode) => {
+                self.settings.unit_price_mode = unit_price_mode;
+                Task::none()
+            }
+            Message::UnitLimitMode(unit_limit_mode) => {
+                self.settings.unit_limit_mode = unit_limit_mode;
+                Task::none()
+            }
             Message::ComputeUniteLimit(unit_limit) => {
                 if let Ok(parsed_limit) = unit_limit.parse::<u32>() {
                     self.settings.unit_limit = parsed_limit;
@@ -225,16 +541,466 @@ impl Lich {
                 LPrograms::set_new_buffer_authority(self.programs.clone(), self.settings.clone()),
                 Message::SignatureToDisplay,
             ),
-            Message::SetNewBufferAuthInput(new_auth) =>{
+            Message::SetNewBufferAuthInput(new_auth) => {
                 self.programs.new_buffer_authority = Some(new_auth);
                 Task::none()
+            }
+            Message::PickBufferAccount => {
+                Task::perform(pick_file(FileType::Keypair), Message::LoadBufferAccount)
+            }
+            Message::LoadBufferAccount(Ok(path_buf)) => {
+                let buffer_account = match load_keypair_from_file(path_buf) {
+                    Ok(buffer_account) => buffer_account,
+                    Err(err) => return Task::done(Message::LoadBufferAccount(Err(err))),
+                };
+                self.programs.resume_buffer = Some(buffer_account.pubkey().to_string());
+                self.programs.buffer_account = buffer_account.into();
+                self.programs.buffer_seed_phrase = None;
+                self.programs.buffer_keypair_path = None;
+                self.programs.is_data_writed = false;
+                Task::none()
+            }
+            Message::LoadBufferAccount(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::ResumeBufferInput(address) => {
+                self.programs.resume_buffer = if address.is_empty() {
+                    None
+                } else {
+                    Some(address)
+                };
+                self.programs.is_data_writed = false;
+                Task::none()
+            }
+            Message::RefreshLocalBuffers => Task::perform(
+                load_local_buffers(self.settings.rpc_client.clone()),
+                Message::LocalBuffers,
+            ),
+            Message::LocalBuffers(Ok(local_buffers)) => {
+                self.buffers.local = local_buffers;
+                Task::none()
+            }
+            Message::LocalBuffers(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::CloseBuffer => Task::perform(
+                LPrograms::close_buffer(self.programs.clone(), self.settings.clone()),
+                Message::BufferClosed,
+            ),
+            Message::CloseBufferTargetInput(target) => {
+                self.programs.close_buffer_target = if target.is_empty() {
+                    None
+                } else {
+                    Some(target)
+                };
+                self.fetch_close_lamports()
+            }
+            Message::CloseRecipientInput(recipient) => {
+                self.programs.close_recipient = Some(recipient);
+                Task::none()
+            }
+            Message::CloseBufferLamports(lamports) => {
+                // a failed lookup just means there's nothing to recover
+                self.programs.close_lamports = lamports.ok();
+                Task::none()
+            }
+            Message::BufferClosed(Ok(signature)) => {
+                if self.programs.close_buffer_pubkey().ok() == Some(self.programs.buffer_pubkey()) {
+                    self.programs.is_data_writed = false;
+                }
+                self.programs.signature = Some(signature);
+                self.programs.close_lamports = None;
+                Task::batch([
+                    Task::perform(
+                        keypair_balance(
+                            self.settings
+                                .keypair_path
+                                .clone()
+                                .unwrap_or(default_keypair_path()),
+                            self.settings.rpc_client.clone(),
+                        ),
+                        Message::AuthoritySolBalance,
+                    ),
+                    self.refresh_fee_payer_balance(),
+                    self.refresh_buffers(),
+                ])
+            }
+            Message::BufferClosed(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::RefreshOwnedBuffers => Task::perform(
+                load_owned_buffers(
+                    self.settings.rpc_client.clone(),
+                    self.settings.keypair.pubkey(),
+                    self.programs.program_bytes.clone(),
+                ),
+                Message::OwnedBuffers,
+            ),
+            Message::OwnedBuffers(Ok(owned_buffers)) => {
+                self.buffers.owned = owned_buffers;
+                Task::none()
+            }
+            Message::OwnedBuffers(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::SelectBuffer(buffer_address, matches_program) => {
+                self.programs.resume_buffer = Some(buffer_address.to_string());
+                self.programs.buffer_seed_phrase = None;
+                self.programs.buffer_keypair_path = None;
+                self.programs.buffer_authority_target = None;
+                // a buffer that already holds the loaded program is ready to be deployed
+                self.programs.is_data_writed = matches_program;
+                self.fetch_close_lamports()
+            }
+            Message::SelectBufferAuthorityTarget(buffer_address) => {
+                self.programs.buffer_authority_target = Some(buffer_address);
+                Task::none()
+            }
+            Message::PickSolanaConfig => {
+                Task::perform(pick_file(FileType::Config), Message::LoadSolanaConfig)
+            }
+            Message::LoadSolanaConfig(Ok(path)) => match load_solana_config(&path) {
+                Ok(solana_config) => {
+                    // the previous keypair stays loaded when the configured one can't be read
+                    let keypair = self.settings.apply_solana_config(solana_config);
+                    let mut tasks = vec![
+                        self.reload_authority(),
+                        self.detect_cluster(),
+                        Task::done(Message::RefreshLocalBuffers),
+                    ];
+                    if let Err(err) = keypair {
+                        tasks.push(Task::done(Message::LoadProgramAuthority(Err(err))));
+                    }
+                    Task::batch(tasks)
+                }
+                Err(err) => Task::done(Message::LoadSolanaConfig(Err(err))),
+            },
+            Message::LoadSolanaConfig(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::SaveSolanaConfig => match self.settings.solana_config() {
+                Some(solana_config) => Task::perform(
+                    async move { save_solana_config(&solana_config) },
+                    Message::SolanaConfigSaved,
+                ),
+                None => Task::done(Message::SolanaConfigSaved(Err(Error::InvalidConfigFile))),
             },
+            Message::SolanaConfigSaved(Ok(())) => Task::none(),
+            Message::SolanaConfigSaved(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::WebsocketUrl(websocket_url) => {
+                self.settings.websocket_url = websocket_url;
+                Task::none()
+            }
+            Message::Commitment(commitment) => {
+                self.settings.commitment = CommitmentConfig { commitment };
+                self.settings.set_rpc_url(self.settings.rpc_client.url());
+                Task::none()
+            }
+            Message::RpcUrlInput(rpc_url) => {
+                self.settings.rpc_url_input = rpc_url;
+                Task::none()
+            }
+            Message::SelectClusterProfile(profile) => {
+                let keypair = self.settings.apply_cluster_profile(&profile);
+                let mut tasks = vec![
+                    self.reload_authority(),
+                    self.detect_cluster(),
+                    Task::done(Message::RefreshLocalBuffers),
+                    self.persist_cluster_profiles(),
+                ];
+                if let Err(err) = keypair {
+                    tasks.push(Task::done(Message::LoadProgramAuthority(Err(err))));
+                }
+                if let Some(path) = profile.program_keypair_path {
+                    tasks.push(Task::done(Message::LoadProgramAccount(Ok(path))));
+                }
+                Task::batch(tasks)
+            }
+            Message::ProfileNameInput(name) => {
+                self.settings.profile_name_input = name;
+                Task::none()
+            }
+            Message::SaveClusterProfile => {
+                let profile = self
+                    .settings
+                    .current_profile(self.programs.program_account_path.clone());
+                if profile.name.is_empty() {
+                    return Task::none();
+                }
+                self.settings.cluster_profiles.upsert(profile);
+                self.persist_cluster_profiles()
+            }
+            Message::AppSettingsSaved(Ok(())) => Task::none(),
+            Message::AppSettingsSaved(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::ResetSettings => {
+                Task::perform(async { reset_app_settings() }, Message::SettingsReset)
+            }
+            Message::SettingsReset(Ok(())) => {
+                self.settings = LSettings::default();
+                self.programs.program_account = None;
+                self.programs.program_account_path = None;
+                self.programs.program_bytes = Vec::new();
+                self.programs.is_data_writed = false;
+                Task::batch([
+                    self.reload_authority(),
+                    Task::done(Message::RefreshLocalBuffers),
+                ])
+            }
+            Message::SettingsReset(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::EstimateCost => Task::perform(
+                LPrograms::estimate_cost(self.programs.clone(), self.settings.clone()),
+                Message::CostEstimated,
+            ),
+            Message::MaxProgramLenInput(max_program_len) => {
+                self.settings.max_program_len_input = max_program_len;
+                Task::done(Message::EstimateCost)
+            }
+            Message::ProgramAddressInput(program_address) => {
+                self.programs.program_address_input = if program_address.is_empty() {
+                    None
+                } else {
+                    Some(program_address)
+                };
+                Task::none()
+            }
+            Message::NewUpgradeAuthorityInput(new_authority) => {
+                // a typed authority replaces the loaded keypair
+                self.programs.new_upgrade_authority_keypair = None;
+                self.programs.new_upgrade_authority = Some(new_authority);
+                Task::none()
+            }
+            Message::PickNewUpgradeAuthority => Task::perform(
+                pick_file(FileType::Keypair),
+                Message::LoadNewUpgradeAuthority,
+            ),
+            Message::LoadNewUpgradeAuthority(Ok(path)) => {
+                let new_authority = match load_keypair_from_file(path) {
+                    Ok(new_authority) => new_authority,
+                    Err(err) => return Task::done(Message::LoadNewUpgradeAuthority(Err(err))),
+                };
+                self.programs.new_upgrade_authority = Some(new_authority.pubkey().to_string());
+                self.programs.new_upgrade_authority_keypair = Some(new_authority.into());
+                Task::none()
+            }
+            Message::LoadNewUpgradeAuthority(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::SetUpgradeAuthority => Task::perform(
+                LPrograms::set_upgrade_authority(self.programs.clone(), self.settings.clone()),
+                Message::SignatureToDisplay,
+            ),
+            Message::ImmutableConfirmationInput(confirmation) => {
+                self.programs.immutable_confirmation = Some(confirmation);
+                Task::none()
+            }
+            Message::MakeImmutable => {
+                // the confirmation is only good for one attempt
+                let programs = self.programs.clone();
+                self.programs.immutable_confirmation = None;
+                Task::perform(
+                    LPrograms::make_immutable(programs, self.settings.clone()),
+                    Message::SignatureToDisplay,
+                )
+            }
+            Message::MultisigAddressInput(multisig_address) => {
+                if let Ok(multisig) = Pubkey::from_str(&multisig_address) {
+                    self.programs.vault_address =
+                        Some(squads_vault_address(&multisig, DEFAULT_VAULT_INDEX).to_string());
+                }
+                self.programs.multisig_address = Some(multisig_address);
+                Task::none()
+            }
+            Message::VaultAddressInput(vault_address) => {
+                self.programs.vault_address = Some(vault_address);
+                Task::none()
+            }
+            Message::SpillAddressInput(spill_address) => {
+                self.programs.spill_address = Some(spill_address);
+                Task::none()
+            }
+            Message::ExportUpgradeProposal => Task::perform(
+                LPrograms::upgrade_proposal(self.programs.clone(), self.settings.clone()),
+                Message::UpgradeProposalExported,
+            ),
+            Message::UpgradeProposalExported(Ok(upgrade_proposal)) => {
+                self.programs.upgrade_proposal = Some(upgrade_proposal);
+                Task::none()
+            }
+            Message::UpgradeProposalExported(Err(err)) => {
+                self.programs.upgrade_proposal = None;
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::OfflineAuthorityInput(offline_authority) => {
+                self.programs.offline_authority = Some(offline_authority);
+                Task::none()
+            }
+            Message::NonceAddressInput(nonce_address) => {
+                self.programs.nonce_address = Some(nonce_address);
+                Task::done(Message::RefreshNonce)
+            }
+            Message::CreateNonceAccount => Task::perform(
+                LPrograms::create_nonce_account(self.settings.clone()),
+                Message::NonceAccountCreated,
+            ),
+            Message::NonceAccountCreated(Ok((nonce_address, signature))) => {
+                self.programs.nonce_address = Some(nonce_address.to_string());
+                self.programs.signature = Some(signature);
+                Task::done(Message::RefreshNonce)
+            }
+            Message::NonceAccountCreated(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::RefreshNonce => Task::perform(
+                LPrograms::nonce_info(self.programs.clone(), self.settings.clone()),
+                Message::NonceInfoLoaded,
+            ),
+            Message::NonceInfoLoaded(nonce_info) => {
+                // a failed lookup just means there's no usable nonce account yet
+                self.programs.nonce_info = nonce_info.ok();
+                Task::none()
+            }
+            Message::AdvanceNonce => Task::perform(
+                LPrograms::advance_nonce(self.programs.clone(), self.settings.clone()),
+                Message::SignatureToDisplay,
+            ),
+            Message::UseNonce(use_nonce) => {
+                self.programs.use_nonce = use_nonce;
+                Task::none()
+            }
+            Message::ExportOfflineTransaction(kind) => Task::perform(
+                LPrograms::offline_transaction(self.programs.clone(), self.settings.clone(), kind),
+                Message::OfflineTransactionBuilt,
+            ),
+            Message::OfflineTransactionBuilt(Ok(offline_transaction)) => {
+                self.programs.offline_transaction = Some(offline_transaction);
+                Task::none()
+            }
+            Message::OfflineTransactionBuilt(Err(err)) => {
+                self.programs.offline_transaction = None;
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::PresignerInput(presigner) => {
+                self.programs.presigner_input = Some(presigner);
+                Task::none()
+            }
+            Message::AddPresigner => match self.programs.add_presigner() {
+                Ok(()) => Task::none(),
+                Err(err) => {
+                    self.error = Some(err);
+                    Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+                }
+            },
+            Message::SubmitOfflineTransaction => Task::perform(
+                LPrograms::submit_offline_transaction(self.programs.clone(), self.settings.clone()),
+                Message::SignatureToDisplay,
+            ),
+            Message::AutoExtend(auto_extend) => {
+                self.settings.auto_extend = auto_extend;
+                Task::done(Message::EstimateCost)
+            }
+            Message::CostEstimated(Ok(cost_estimate)) => {
+                self.programs.cost_estimate = Some(cost_estimate);
+                Task::none()
+            }
+            Message::CostEstimated(Err(err)) => {
+                self.programs.cost_estimate = None;
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::ClusterProfilesSaved(Ok(())) => Task::none(),
+            Message::ClusterProfilesSaved(Err(err)) => {
+                self.error = Some(err);
+                Task::perform(Lich::sleep(), |_| Message::ErrorCleared)
+            }
+            Message::ClusterDetected(rpc_url, is_mainnet) => {
+                if rpc_url == self.settings.rpc_client.url() {
+                    self.settings.is_mainnet = is_mainnet;
+                }
+                Task::none()
+            }
+        }
+    }
+
+    // reloads the authority keypair, which also refreshes its balance and owned buffers,
+    // and the fee payer balance
+    fn reload_authority(&self) -> Task<Message> {
+        let keypair_path = self
+            .settings
+            .keypair_path
+            .clone()
+            .unwrap_or(default_keypair_path());
+        Task::batch([
+            Task::done(Message::LoadProgramAuthority(Ok(keypair_path))),
+            self.refresh_fee_payer_balance(),
+        ])
+    }
+
+    // the authority balance is refreshed on its own, a separate fee payer needs this too
+    fn refresh_fee_payer_balance(&self) -> Task<Message> {
+        match self.settings.fee_payer_path.clone() {
+            Some(fee_payer_path) => Task::perform(
+                keypair_balance(fee_payer_path, self.settings.rpc_client.clone()),
+                Message::FeePayerSolBalance,
+            ),
+            None => Task::none(),
+        }
+    }
+
+    // the mainnet warning can't rely on the url alone
+    fn detect_cluster(&self) -> Task<Message> {
+        let rpc_client = self.settings.rpc_client.clone();
+        let rpc_url = rpc_client.url();
+        Task::perform(is_mainnet(rpc_client), move |is_mainnet| {
+            Message::ClusterDetected(rpc_url.clone(), is_mainnet)
+        })
+    }
+
+    fn persist_cluster_profiles(&self) -> Task<Message> {
+        let cluster_profiles = self.settings.cluster_profiles.clone();
+        Task::perform(
+            async move { save_cluster_profiles(&cluster_profiles) },
+            Message::ClusterProfilesSaved,
+        )
+    }
+
+    fn refresh_buffers(&self) -> Task<Message> {
+        Task::batch([
+            Task::done(Message::RefreshLocalBuffers),
+            Task::done(Message::RefreshOwnedBuffers),
+        ])
+    }
+
+    fn fetch_close_lamports(&self) -> Task<Message> {
+        match self.programs.close_buffer_pubkey() {
+            Ok(buffer_address) => Task::perform(
+                buffer_lamports(self.settings.rpc_client.clone(), buffer_address),
+                Message::CloseBufferLamports,
+            ),
+            Err(err) => Task::done(Message::CloseBufferLamports(Err(err))),
         }
     }
 
     fn subscription(&self) -> Subscription<Message> {
         match self.programs.is_writing_data {
-            true => Progress::run_susbcription(1, self.programs.clone(), self.settings.clone())
+            true => LPrograms::run_susbcription(1, self.programs.clone(), self.settings.clone())
                 .map(|values| Message::UpdateProgress(values.1)),
             false => Subscription::none(),
         }
@@ -244,27 +1010,47 @@ impl Lich {
         let settings = self.settings.view(&self.programs);
         let is_data_writed = self.programs.deployed_message_element();
         let deploy_btn = self.programs.deploy_or_upgrade_btn();
+        let verification = self.programs.verification_items();
+        let inspector = self.programs.inspector_items();
         let set_new_auth = self.programs.set_new_buffer_auth_items();
+        let close_buffer = self.programs.close_buffer_items();
+        let upgrade_authority = self.programs.upgrade_authority_items();
+        let upgrade_proposal = self.programs.upgrade_proposal_items();
+        let nonce = self.programs.nonce_items();
+        let offline_signer = self.programs.offline_signer_items();
         let buffer_acc = self.programs.buffer_address();
         let display_error = error(&self.error);
         let tx_progress = self.programs.tx_progress();
         let write_data_btn = self.programs.write_data_btn();
+        let cost_estimate = self.programs.cost_estimate_items();
         let signature = self.programs.signature_text_with_copy();
+        let local_buffers = self.buffers.local_buffers_view();
+        let owned_buffers = self.buffers.owned_buffers_view();
 
-        container(
+        container(scrollable(
             column![
                 settings,
                 buffer_acc,
+                cost_estimate,
                 tx_progress,
                 write_data_btn,
                 display_error,
                 is_data_writed,
                 deploy_btn,
+                verification,
                 set_new_auth,
-                signature
+                close_buffer,
+                upgrade_authority,
+                upgrade_proposal,
+                nonce,
+                offline_signer,
+                inspector,
+                signature,
+                owned_buffers,
+                local_buffers
             ]
             .spacing(5),
-        )
+        ))
         .padding(30)
         .into()
     }
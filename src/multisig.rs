@@ -0,0 +1,32 @@
+use solana_sdk::{pubkey, pubkey::Pubkey};
+
+use crate::transactions::SerializedMessage;
+
+pub const SQUADS_PROGRAM_ID: Pubkey = pubkey!("SQDS4ep65T869zMMBKyuUq6MD6Q2mGo4NYYTX2tc1eu");
+pub const DEFAULT_VAULT_INDEX: u8 = 0;
+
+// squads v4 vault that signs for the multisig, seeds are
+// ["multisig", multisig, "vault", vault index]
+pub fn squads_vault_address(multisig_address: &Pubkey, vault_index: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"multisig",
+            multisig_address.as_ref(),
+            b"vault",
+            &[vault_index],
+        ],
+        &SQUADS_PROGRAM_ID,
+    )
+    .0
+}
+
+// the unsigned upgrade a multisig has to approve, the message is pasted into its UI
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpgradeProposal {
+    pub program_address: Pubkey,
+    pub buffer_address: Pubkey,
+    pub vault_address: Pubkey,
+    // receives the buffer lamports once the upgrade consumes it
+    pub spill_address: Pubkey,
+    pub message: SerializedMessage,
+}
@@ -0,0 +1,45 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    hash::Hash,
+    nonce::state::{State, Versions},
+    pubkey::Pubkey,
+    system_program,
+};
+
+use crate::errors::Error;
+
+// a durable nonce stands in for the recent blockhash, so the transaction doesn't expire
+// until the nonce is advanced
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NonceInfo {
+    pub address: Pubkey,
+    // has to sign every transaction that uses the nonce
+    pub authority: Pubkey,
+    pub blockhash: Hash,
+}
+
+pub async fn get_nonce_info(
+    rpc_client: &RpcClient,
+    nonce_address: &Pubkey,
+) -> Result<NonceInfo, Error> {
+    let account = rpc_client
+        .get_account(nonce_address)
+        .await
+        .map_err(|e| Error::RpcError(e))?;
+
+    if account.owner != system_program::id() {
+        return Err(Error::InvalidNonceAccount);
+    }
+
+    match bincode::deserialize::<Versions>(&account.data) {
+        Ok(versions) => match versions.state() {
+            State::Initialized(data) => Ok(NonceInfo {
+                address: *nonce_address,
+                authority: data.authority,
+                blockhash: data.blockhash(),
+            }),
+            State::Uninitialized => Err(Error::InvalidNonceAccount),
+        },
+        Err(_) => Err(Error::InvalidNonceAccount),
+    }
+}
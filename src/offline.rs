@@ -0,0 +1,220 @@
+use std::{str::FromStr, sync::Arc};
+
+use solana_sdk::{
+    message::Message,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+
+use crate::{
+    errors::Error,
+    transactions::{decode_message, SerializedMessage},
+};
+
+// what the offline authority is asked to sign, the message is built around a durable nonce
+// so it doesn't expire while it travels to the cold signer and back
+#[derive(Debug, Clone)]
+pub enum OfflineAction {
+    // the program keypair is local, it signs when the transaction is submitted
+    Deploy {
+        program_keypair: Arc<Keypair>,
+        buffer_address: Pubkey,
+    },
+    Upgrade {
+        program_address: Pubkey,
+        buffer_address: Pubkey,
+    },
+    // None makes the program immutable
+    SetUpgradeAuthority {
+        program_address: Pubkey,
+        new_authority: Option<Pubkey>,
+    },
+}
+
+// a transaction whose authority signs elsewhere, local keypairs sign it right away and the
+// remaining signatures are pasted back before it can be submitted
+#[derive(Debug, Clone, PartialEq)]
+pub struct OfflineTransaction {
+    pub transaction: Transaction,
+}
+
+impl OfflineTransaction {
+    // keypairs that aren't signers of the message are ignored
+    pub fn new(message: Message, local_signers: &[&Keypair]) -> Result<Self, Error> {
+        let mut offline_transaction = Self {
+            transaction: Transaction::new_unsigned(message),
+        };
+        offline_transaction.sign_local(local_signers)?;
+        Ok(offline_transaction)
+    }
+
+    // rebuilds it from the exported message, the way the command line submits it
+    pub fn from_encoded(encoded: &str, local_signers: &[&Keypair]) -> Result<Self, Error> {
+        Self::new(decode_message(encoded)?, local_signers)
+    }
+
+    pub fn sign_local(&mut self, local_signers: &[&Keypair]) -> Result<(), Error> {
+        let signer_keys = self.signer_keys();
+        let signers: Vec<&Keypair> = local_signers
+            .iter()
+            .copied()
+            .filter(|signer| signer_keys.contains(&signer.pubkey()))
+            .collect();
+        let recent_blockhash = self.transaction.message.recent_blockhash;
+        self.transaction
+            .try_partial_sign(signers.as_slice(), recent_blockhash)
+            .map_err(|_| Error::InvalidSignature)
+    }
+
+    // the signature is checked against the message before it's accepted
+    pub fn add_signature(&mut self, signer: &Pubkey, signature: &Signature) -> Result<(), Error> {
+        let index = self
+            .signer_keys()
+            .iter()
+            .position(|signer_key| signer_key == signer)
+            .ok_or(Error::InvalidSignature)?;
+        if !signature.verify(signer.as_ref(), &self.transaction.message_data()) {
+            return Err(Error::InvalidSignature);
+        }
+        self.transaction.signatures[index] = *signature;
+        Ok(())
+    }
+
+    pub fn signer_keys(&self) -> Vec<Pubkey> {
+        self.transaction
+            .message
+            .signer_keys()
+            .into_iter()
+            .copied()
+            .collect()
+    }
+
+    pub fn missing_signers(&self) -> Vec<Pubkey> {
+        self.signer_keys()
+            .into_iter()
+            .zip(&self.transaction.signatures)
+            .filter(|(_, signature)| **signature == Signature::default())
+            .map(|(signer, _)| signer)
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing_signers().is_empty()
+    }
+
+    // what gets handed to the cold signer
+    pub fn message(&self) -> SerializedMessage {
+        SerializedMessage::from(&self.transaction.message)
+    }
+}
+
+// same format as the solana cli `--signer` argument, PUBKEY=SIGNATURE
+pub fn parse_presigner(presigner: &str) -> Result<(Pubkey, Signature), Error> {
+    let (signer, signature) = presigner
+        .trim()
+        .split_once('=')
+        .ok_or(Error::InvalidSignature)?;
+    let signer = Pubkey::from_str(signer.trim()).map_err(|_| Error::InvalidSignature)?;
+    let signature = Signature::from_str(signature.trim()).map_err(|_| Error::InvalidSignature)?;
+    Ok((signer, signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{hash::Hash, system_instruction::transfer};
+
+    use super::*;
+
+    // the payer signs locally, the authority is the cold signer
+    fn offline_transaction(payer: &Keypair, authority: &Pubkey) -> OfflineTransaction {
+        let recipient = Pubkey::new_unique();
+        let message = Message::new_with_blockhash(
+            &[
+                transfer(&payer.pubkey(), &recipient, 1),
+                transfer(authority, &recipient, 1),
+            ],
+            Some(&payer.pubkey()),
+            &Hash::new_unique(),
+        );
+        OfflineTransaction::new(message, &[payer]).unwrap()
+    }
+
+    #[test]
+    fn local_signers_sign_right_away() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let offline_transaction = offline_transaction(&payer, &authority.pubkey());
+        assert_eq!(
+            offline_transaction.missing_signers(),
+            vec![authority.pubkey()]
+        );
+        assert!(!offline_transaction.is_complete());
+    }
+
+    #[test]
+    fn add_signature_completes_the_transaction() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let mut offline_transaction = offline_transaction(&payer, &authority.pubkey());
+
+        let signature = authority.sign_message(&offline_transaction.transaction.message_data());
+        offline_transaction
+            .add_signature(&authority.pubkey(), &signature)
+            .unwrap();
+        assert!(offline_transaction.is_complete());
+        assert!(offline_transaction.transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn add_signature_rejects_a_signature_of_another_key() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let mut offline_transaction = offline_transaction(&payer, &authority.pubkey());
+
+        let signature =
+            Keypair::new().sign_message(&offline_transaction.transaction.message_data());
+        assert!(offline_transaction
+            .add_signature(&authority.pubkey(), &signature)
+            .is_err());
+        assert_eq!(
+            offline_transaction.missing_signers(),
+            vec![authority.pubkey()]
+        );
+    }
+
+    #[test]
+    fn add_signature_rejects_a_key_outside_the_message() {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let mut offline_transaction = offline_transaction(&payer, &authority.pubkey());
+
+        let stranger = Keypair::new();
+        let signature = stranger.sign_message(&offline_transaction.transaction.message_data());
+        assert!(offline_transaction
+            .add_signature(&stranger.pubkey(), &signature)
+            .is_err());
+    }
+
+    #[test]
+    fn parse_presigner_reads_pubkey_and_signature() {
+        let signer = Keypair::new();
+        let signature = signer.sign_message(b"message");
+        let presigner = format!(" {} = {} ", signer.pubkey(), signature);
+        assert_eq!(
+            parse_presigner(&presigner).ok(),
+            Some((signer.pubkey(), signature))
+        );
+    }
+
+    #[test]
+    fn parse_presigner_rejects_malformed_input() {
+        let signer = Keypair::new();
+        let signature = signer.sign_message(b"message");
+        assert!(parse_presigner(&signer.pubkey().to_string()).is_err());
+        assert!(parse_presigner(&format!("{}:{}", signer.pubkey(), signature)).is_err());
+        assert!(parse_presigner(&format!("not-a-pubkey={}", signature)).is_err());
+        assert!(parse_presigner(&format!("{}=not-a-signature", signer.pubkey())).is_err());
+    }
+}
@@ -1,311 +1,410 @@
-use iced::futures::channel::mpsc::Sender;
-use iced::futures::future::join_all;
 use iced::futures::{Stream, StreamExt};
 use iced::stream::try_channel;
-use iced::widget::{button, column, progress_bar, row, text, text_input};
+use iced::widget::{button, checkbox, column, progress_bar, row, text, text_input};
 use iced::{color, Alignment, Element, Subscription};
-use solana_client::{rpc_client::SerializableTransaction, rpc_config::RpcSendTransactionConfig};
+use lich_deployer::cost::CostEstimate;
+use lich_deployer::deployer::{
+    save_program_bytes, BufferTarget, ProgramTarget, Progress, UpgradeAuthorityChange,
+};
+use lich_deployer::errors::Error;
+use lich_deployer::inspect::ProgramInfo;
+use lich_deployer::keypair::new_buffer_keypair;
+use lich_deployer::multisig::UpgradeProposal;
+use lich_deployer::nonce::NonceInfo;
+use lich_deployer::offline::{parse_presigner, OfflineAction, OfflineTransaction};
+use lich_deployer::verify::ProgramVerification;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::transaction::Transaction;
 use solana_sdk::{
-    commitment_config::{CommitmentConfig, CommitmentLevel},
     signature::{Keypair, Signature},
     signer::Signer,
 };
-use solana_transaction_status::UiTransactionEncoding;
+use std::ops::Range;
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::{fs, sync::Arc, time::Duration};
-use tokio::task::JoinHandle;
-use tokio::{spawn, time};
+use std::sync::Arc;
 
 use crate::components::copy_to_cliboard_btn;
-use crate::instructions::{
-    create_buffer_account, deploy_program, set_new_buffer_auth, upgrade_program, write_data,
-};
+use crate::dialogs::{save_file, FileType};
 use crate::settings::LSettings;
-use crate::transactions::send_tx_and_verify_status;
-use crate::{errors::Error, Message};
+use crate::Message;
 
-pub const MAX_WRITE_LEN: usize = 960;
-pub const PROGRAM_EXTRA_SPACE: usize = 45;
+// which transaction the offline authority is asked to sign
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OfflineKind {
+    DeployOrUpgrade,
+    SetUpgradeAuthority,
+    MakeImmutable,
+}
 
 #[derive(Debug, Clone)]
 pub struct LPrograms {
     pub buffer_account: Arc<Keypair>,
     pub program_account: Option<Arc<Keypair>>,
+    pub program_account_path: Option<PathBuf>,
+    // upgrades only need the program id, a typed one wins over the loaded keypair
+    pub program_address_input: Option<String>,
     pub program_bytes: Vec<u8>,
     pub transactions: (usize, usize),
     pub is_data_writed: bool,
     pub is_writing_data: bool,
+    // the buffer is read back once the writes land, deploying waits until it matches
+    pub is_verifying: bool,
+    pub mismatched_ranges: Vec<Range<usize>>,
     pub signature: Option<Signature>,
     pub new_buffer_authority: Option<String>,
+    pub resume_buffer: Option<String>,
+    pub buffer_seed_phrase: Option<String>,
+    // where the keypair of the buffer being written was saved, shown so it can be recovered
+    pub buffer_keypair_path: Option<PathBuf>,
+    // unit price of the current retry round, None until a round of writes fails to land
+    pub retry_unit_price: Option<u64>,
+    pub close_buffer_target: Option<String>,
+    pub close_recipient: Option<String>,
+    pub close_lamports: Option<u64>,
+    pub buffer_authority_target: Option<Pubkey>,
+    pub cost_estimate: Option<CostEstimate>,
+    pub new_upgrade_authority: Option<String>,
+    // set when the new authority keypair is loaded, the transfer then uses the checked variant
+    pub new_upgrade_authority_keypair: Option<Arc<Keypair>>,
+    pub immutable_confirmation: Option<String>,
+    pub multisig_address: Option<String>,
+    // filled with the derived vault when a multisig address is typed, it can be edited
+    pub vault_address: Option<String>,
+    pub spill_address: Option<String>,
+    pub upgrade_proposal: Option<UpgradeProposal>,
+    pub offline_authority: Option<String>,
+    pub nonce_address: Option<String>,
+    pub nonce_info: Option<NonceInfo>,
+    // the deploy or upgrade is signed on the nonce instead of a recent blockhash
+    pub use_nonce: bool,
+    pub presigner_input: Option<String>,
+    pub offline_transaction: Option<OfflineTransaction>,
+    // the deployed executable compared with the loaded .so, after a deploy or on demand
+    pub verification: Option<ProgramVerification>,
+    // any program id, the loaded program when it's left empty
+    pub inspect_input: Option<String>,
+    pub program_info: Option<ProgramInfo>,
+    // where the last dumped executable was saved
+    pub dump_path: Option<PathBuf>,
 }
 
 impl Default for LPrograms {
     fn default() -> Self {
+        let (buffer_account, buffer_seed_phrase) = new_buffer_keypair();
         Self {
-            buffer_account: Keypair::new().into(),
+            buffer_account: buffer_account.into(),
             program_account: None,
+            program_account_path: None,
+            program_address_input: None,
             program_bytes: Vec::new(),
             transactions: (0, 0),
             is_data_writed: false,
             is_writing_data: false,
+            is_verifying: false,
+            mismatched_ranges: Vec::new(),
             signature: None,
             new_buffer_authority: None,
+            resume_buffer: None,
+            buffer_seed_phrase: Some(buffer_seed_phrase),
+            buffer_keypair_path: None,
+            retry_unit_price: None,
+            close_buffer_target: None,
+            close_recipient: None,
+            close_lamports: None,
+            buffer_authority_target: None,
+            cost_estimate: None,
+            new_upgrade_authority: None,
+            new_upgrade_authority_keypair: None,
+            immutable_confirmation: None,
+            multisig_address: None,
+            vault_address: None,
+            spill_address: None,
+            upgrade_proposal: None,
+            offline_authority: None,
+            nonce_address: None,
+            nonce_info: None,
+            use_nonce: false,
+            presigner_input: None,
+            offline_transaction: None,
+            verification: None,
+            inspect_input: None,
+            program_info: None,
+            dump_path: None,
         }
     }
 }
 
-pub const SEND_CFG: RpcSendTransactionConfig = RpcSendTransactionConfig {
-    skip_preflight: true,
-    preflight_commitment: Some(CommitmentLevel::Finalized),
-    encoding: Some(UiTransactionEncoding::Base64),
-    max_retries: Some(3),
-    min_context_slot: None,
-};
-
 impl LPrograms {
-    pub async fn create_buffer_and_write_data(
-        self,
-        settings: LSettings,
-        mut output: Sender<Progress>,
-    ) -> Result<(), Error> {
-        let _ = output.try_send(Progress::Idle);
-        let buffer_acc = self.buffer_account;
-
-        let authority = &settings.keypair;
-        let rpc_client = &settings.rpc_client;
+    pub async fn deploy_or_upgrade(self, settings: LSettings) -> Result<Signature, Error> {
+        let buffer_address = self.buffer_pubkey();
+        // first check if the program account is set
+        let program = self.program_target()?;
+        let nonce_account = match self.use_nonce {
+            true => Some(self.nonce_pubkey()?),
+            false => None,
+        };
 
-        let (recent_blockhash, _) = rpc_client
-            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+        settings
+            .deployer()
+            .with_nonce_account(nonce_account)
+            .deploy_or_upgrade(&program, &buffer_address, &self.program_bytes)
             .await
-            .map_err(|e| Error::RpcError(e))?;
+    }
 
-        let lamports = rpc_client
-            .get_minimum_balance_for_rent_exemption(self.program_bytes.len() + PROGRAM_EXTRA_SPACE)
+    pub async fn set_new_buffer_authority(self, settings: LSettings) -> Result<Signature, Error> {
+        let buffer_address = self.buffer_authority_target.unwrap_or(self.buffer_pubkey());
+        let new_authority = if let Some(new_authority) = &self.new_buffer_authority {
+            Pubkey::from_str(new_authority).map_err(|_| Error::UndefinedNewBufferAuthority)?
+        } else {
+            return Err(Error::UndefinedNewBufferAuthority);
+        };
+        settings
+            .deployer()
+            .set_buffer_authority(&buffer_address, &new_authority)
             .await
-            .unwrap_or(0);
-
-        if lamports >= settings.balance.unwrap_or(0) {
-            return Err(Error::InsufficientSolBalance);
-        }
+    }
 
-        if self.program_bytes.len() == 0 {
-            println!("error");
-            return Err(Error::InvalidProgramLen);
-        }
+    // closes the selected buffer (the current one by default) and sends its lamports to
+    // the recipient, which defaults to the spill account
+    pub async fn close_buffer(self, settings: LSettings) -> Result<Signature, Error> {
+        let buffer_address = self.close_buffer_pubkey()?;
+        let deployer = settings.deployer();
 
-        let buffer_acc_init_tx = create_buffer_account(
-            &buffer_acc,
-            &authority,
-            lamports,
-            &self.program_bytes,
-            recent_blockhash,
-            &settings,
-        )
-        .map_err(|e| Error::InstructionError(e))?;
+        let recipient = match self.close_recipient.as_deref() {
+            Some(recipient) if !recipient.is_empty() => {
+                Pubkey::from_str(recipient).map_err(|_| Error::InvalidRecipient)?
+            }
+            _ => deployer.spill_address(),
+        };
 
-        let _signature =
-            send_tx_and_verify_status(&rpc_client, &buffer_acc_init_tx, SEND_CFG).await;
+        deployer.close_buffer(&buffer_address, &recipient).await
+    }
 
-        let (updated_blockhash, mut last_valid_blockheight) = rpc_client
-            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+    pub async fn estimate_cost(self, settings: LSettings) -> Result<CostEstimate, Error> {
+        let program_address = self.program_target().ok().map(|program| program.pubkey());
+        settings
+            .deployer()
+            .estimate_cost(
+                &self.buffer_target(),
+                program_address.as_ref(),
+                &self.program_bytes,
+            )
             .await
-            .map_err(|e| Error::RpcError(e))?;
-        let mut write_data_txs = write_data(
-            &buffer_acc.pubkey(),
-            &self.program_bytes,
-            &authority,
-            updated_blockhash,
-            MAX_WRITE_LEN,
-            &settings,
-        );
+    }
 
-        let sleep_between_send = 15; // 15 ms to await between each send
-        let batch_size = 250;
-
-        let mut tx_sent = 0;
-        loop {
-            for transaction in &write_data_txs {
-                tx_sent += 1;
-
-                let _ = output.try_send(Progress::Sending {
-                    sent: tx_sent,
-                    total: write_data_txs.len(),
-                });
-                let client = rpc_client.clone();
-                let tx = transaction.clone();
-                spawn(async move {
-                    let _ = client.send_transaction_with_config(&tx, SEND_CFG).await;
-                });
-                time::sleep(Duration::from_millis(sleep_between_send)).await;
+    pub async fn set_upgrade_authority(self, settings: LSettings) -> Result<Signature, Error> {
+        let program_address = self.program_target()?.pubkey();
+        let change = match (
+            &self.new_upgrade_authority_keypair,
+            self.new_upgrade_authority.as_deref(),
+        ) {
+            (Some(new_authority), _) => {
+                UpgradeAuthorityChange::TransferChecked(new_authority.clone())
             }
+            (None, Some(new_authority)) if !new_authority.is_empty() => {
+                UpgradeAuthorityChange::Transfer(
+                    Pubkey::from_str(new_authority)
+                        .map_err(|_| Error::UndefinedNewUpgradeAuthority)?,
+                )
+            }
+            _ => return Err(Error::UndefinedNewUpgradeAuthority),
+        };
+        settings
+            .deployer()
+            .set_upgrade_authority(&program_address, &change)
+            .await
+    }
 
-            let tx_signatures: Vec<Signature> = write_data_txs
-                .iter()
-                .map(|tx| *tx.get_signature())
-                .collect();
-
-            let mut tx_signatures_batches = get_vec_with_batched_data(batch_size, &tx_signatures);
-
-            let check_failed_tx_tasks: Vec<JoinHandle<Vec<Signature>>> = tx_signatures_batches
-                .iter_mut()
-                .map(|chunk_signature| {
-                    let rpc_client = rpc_client.clone();
-                    let mut chunk_signatures = chunk_signature.clone();
-
-                    spawn(async move {
-                        let mut retrys = 0;
-                        let mut tx_to_retry = Vec::new();
-                        let max_retrys = 10;
-
-                        while retrys < max_retrys {
-                            let status_vec = rpc_client
-                                .get_signature_statuses(&chunk_signatures)
-                                .await
-                                .ok()
-                                .map(|v| v.value)
-                                .unwrap_or_default();
-                            let mut failed_signatures = Vec::new();
-
-                            for (i, status) in status_vec.iter().enumerate() {
-                                if status.as_ref().map_or(true, |c| {
-                                    c.err.is_some()
-                                        || (c.confirmation_status.is_none()
-                                            && retrys == max_retrys - 1)
-                                }) {
-                                    failed_signatures.push(chunk_signatures[i]);
-                                }
-                            }
-                            chunk_signatures.retain(|signature| {
-                                let keep = !failed_signatures.contains(signature);
-                                if !keep {
-                                    tx_to_retry.push(*signature);
-                                }
-                                keep
-                            });
-
-                            if chunk_signatures.is_empty() {
-                                break;
-                            }
-
-                            time::sleep(Duration::from_millis(500)).await;
-                            retrys += 1;
-                        }
-                        tx_to_retry
-                    })
-                })
-                .collect();
-
-            let results: Vec<Vec<Signature>> = join_all(check_failed_tx_tasks)
-                .await
-                .into_iter()
-                .filter_map(Result::ok)
-                .collect();
-
-            let tx_to_retry: Vec<Signature> = results.into_iter().flatten().collect();
-
-            write_data_txs = write_data_txs
-                .drain(..)
-                .filter(|tx| tx_to_retry.contains(tx.get_signature()))
-                .collect();
+    pub async fn make_immutable(self, settings: LSettings) -> Result<Signature, Error> {
+        let program_address = self.program_target()?.pubkey();
+        if !self.is_immutable_confirmed() {
+            return Err(Error::ImmutableNotConfirmed);
+        }
+        settings
+            .deployer()
+            .set_upgrade_authority(&program_address, &UpgradeAuthorityChange::Revoke)
+            .await
+    }
 
-            let current_blockheight = rpc_client.get_block_height().await.unwrap_or_default();
+    // the program id has to be typed back before the authority can be revoked
+    pub fn is_immutable_confirmed(&self) -> bool {
+        match (
+            self.program_target(),
+            self.immutable_confirmation.as_deref(),
+        ) {
+            (Ok(program), Some(confirmation)) => {
+                confirmation.trim() == program.pubkey().to_string()
+            }
+            _ => false,
+        }
+    }
 
-            if current_blockheight >= last_valid_blockheight {
-                let (updated_blockhash, last_valid_block_height_updated) = rpc_client
-                    .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
-                    .await
-                    .map_err(|e| Error::RpcError(e))?;
+    // the buffer has to be handed to the vault first, the spill account defaults to the vault
+    pub async fn upgrade_proposal(self, settings: LSettings) -> Result<UpgradeProposal, Error> {
+        let program_address = self.program_target()?.pubkey();
+        let buffer_address = self.buffer_authority_target.unwrap_or(self.buffer_pubkey());
+        let vault_address = match self.vault_address.as_deref() {
+            Some(vault_address) if !vault_address.is_empty() => {
+                Pubkey::from_str(vault_address).map_err(|_| Error::InvalidVaultAddress)?
+            }
+            _ => return Err(Error::InvalidVaultAddress),
+        };
+        let spill_address = match self.spill_address.as_deref() {
+            Some(spill_address) if !spill_address.is_empty() => {
+                Pubkey::from_str(spill_address).map_err(|_| Error::InvalidSpillAddress)?
+            }
+            _ => vault_address,
+        };
+        settings
+            .deployer()
+            .upgrade_proposal(
+                &program_address,
+                &buffer_address,
+                &vault_address,
+                &spill_address,
+            )
+            .await
+    }
 
-                last_valid_blockheight = last_valid_block_height_updated;
+    pub async fn offline_transaction(
+        self,
+        settings: LSettings,
+        kind: OfflineKind,
+    ) -> Result<OfflineTransaction, Error> {
+        let offline_authority = match self.offline_authority.as_deref() {
+            Some(offline_authority) if !offline_authority.is_empty() => {
+                Pubkey::from_str(offline_authority).map_err(|_| Error::InvalidOfflineAuthority)?
+            }
+            _ => return Err(Error::InvalidOfflineAuthority),
+        };
+        let nonce_address = self.nonce_pubkey()?;
 
-                for transaction in write_data_txs.iter_mut() {
-                    transaction.sign(&[&authority], updated_blockhash);
+        let deployer = settings.deployer();
+        let program = self.program_target()?;
+        let action = match kind {
+            OfflineKind::DeployOrUpgrade => {
+                let buffer_address = self.buffer_authority_target.unwrap_or(self.buffer_pubkey());
+                match program {
+                    ProgramTarget::Keypair(program_keypair)
+                        if !deployer
+                            .is_program_deployed(&program_keypair.pubkey())
+                            .await =>
+                    {
+                        OfflineAction::Deploy {
+                            program_keypair,
+                            buffer_address,
+                        }
+                    }
+                    program => OfflineAction::Upgrade {
+                        program_address: program.pubkey(),
+                        buffer_address,
+                    },
                 }
             }
-
-            if tx_to_retry.is_empty() {
-                let _ = output.try_send(Progress::Completed {
-                    buffer_account: buffer_acc,
-                });
-                break;
+            OfflineKind::SetUpgradeAuthority => OfflineAction::SetUpgradeAuthority {
+                program_address: program.pubkey(),
+                new_authority: Some(
+                    self.new_upgrade_authority
+                        .as_deref()
+                        .and_then(|new_authority| Pubkey::from_str(new_authority).ok())
+                        .ok_or(Error::UndefinedNewUpgradeAuthority)?,
+                ),
+            },
+            OfflineKind::MakeImmutable => {
+                if !self.is_immutable_confirmed() {
+                    return Err(Error::ImmutableNotConfirmed);
+                }
+                OfflineAction::SetUpgradeAuthority {
+                    program_address: program.pubkey(),
+                    new_authority: None,
+                }
             }
+        };
+        deployer
+            .offline_transaction(&action, &offline_authority, &nonce_address)
+            .await
+    }
 
-            tx_sent = 0;
-        }
-        Ok(())
+    // the new nonce account address is returned with the signature
+    pub async fn create_nonce_account(settings: LSettings) -> Result<(Pubkey, Signature), Error> {
+        let nonce_account = Keypair::new();
+        let signature = settings
+            .deployer()
+            .create_nonce_account(&nonce_account)
+            .await?;
+        Ok((nonce_account.pubkey(), signature))
     }
 
-    pub async fn deploy_or_upgrade(self, settings: LSettings) -> Result<Signature, Error> {
-        let rpc_client = &settings.rpc_client;
-        // first check if the program account is set
-        let program_account = if let Some(valid_program_acc) = &self.program_account {
-            valid_program_acc
-        } else {
-            return Err(Error::ProgramAccountNotLoaded);
-        };
+    pub async fn nonce_info(self, settings: LSettings) -> Result<NonceInfo, Error> {
+        let nonce_address = self.nonce_pubkey()?;
+        settings.deployer().nonce_info(&nonce_address).await
+    }
 
-        // if its err means that the account is not initialized yet and there is no data related to
-        let has_data = !rpc_client
-            .get_account(&program_account.pubkey())
-            .await
-            .is_err();
+    pub async fn advance_nonce(self, settings: LSettings) -> Result<Signature, Error> {
+        let nonce_address = self.nonce_pubkey()?;
+        settings.deployer().advance_nonce(&nonce_address).await
+    }
+
+    pub fn nonce_pubkey(&self) -> Result<Pubkey, Error> {
+        self.nonce_address
+            .as_deref()
+            .and_then(|nonce_address| Pubkey::from_str(nonce_address).ok())
+            .ok_or(Error::InvalidNonceAccount)
+    }
+
+    // adds the pasted PUBKEY=SIGNATURE to the offline transaction
+    pub fn add_presigner(&mut self) -> Result<(), Error> {
+        let offline_transaction = self
+            .offline_transaction
+            .as_mut()
+            .ok_or(Error::InvalidMessage)?;
+        let (signer, signature) = parse_presigner(self.presigner_input.as_deref().unwrap_or(""))?;
+        offline_transaction.add_signature(&signer, &signature)?;
+        self.presigner_input = None;
+        Ok(())
+    }
 
-        let (blockhash, _) = rpc_client
-            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+    pub async fn submit_offline_transaction(self, settings: LSettings) -> Result<Signature, Error> {
+        let offline_transaction = self.offline_transaction.ok_or(Error::InvalidMessage)?;
+        settings
+            .deployer()
+            .send_offline_transaction(&offline_transaction)
             .await
-            .map_err(|e| Error::RpcError(e))?;
+    }
 
-        let tx: Transaction;
+    pub fn program_target(&self) -> Result<ProgramTarget, Error> {
+        match (self.program_address_input.as_deref(), &self.program_account) {
+            (Some(address), _) if !address.is_empty() => Pubkey::from_str(address)
+                .map(ProgramTarget::Address)
+                .map_err(|_| Error::InvalidProgramAccount),
+            (_, Some(program_account)) => Ok(ProgramTarget::Keypair(program_account.clone())),
+            _ => Err(Error::ProgramAccountNotLoaded),
+        }
+    }
 
-        // so, if has data, we just upgrade the program
-        if has_data {
-            tx = upgrade_program(
-                &program_account,
-                &self.buffer_account.pubkey(),
-                &settings.keypair,
-                blockhash,
-            );
-        } else {
-            // if not, we deploy, in this part the program keypair needs to sign
-            let lamports = rpc_client
-                .get_minimum_balance_for_rent_exemption(
-                    self.program_bytes.len() + PROGRAM_EXTRA_SPACE,
-                )
-                .await
-                .unwrap_or(0);
+    pub fn buffer_target(&self) -> BufferTarget {
+        match self.resume_buffer {
+            Some(_) => BufferTarget::Resume(self.buffer_pubkey()),
+            None => BufferTarget::New(self.buffer_account.clone()),
+        }
+    }
 
-            tx = deploy_program(
-                &settings.keypair,
-                &program_account,
-                &self.buffer_account.pubkey(),
-                &self.program_bytes,
-                lamports,
-                blockhash,
-            )?;
+    pub fn close_buffer_pubkey(&self) -> Result<Pubkey, Error> {
+        match self.close_buffer_target.as_deref() {
+            Some(target) if !target.is_empty() => {
+                Pubkey::from_str(target).map_err(|_| Error::InvalidBufferAccount)
+            }
+            _ => Ok(self.buffer_pubkey()),
         }
-        let signature = send_tx_and_verify_status(&rpc_client, &tx, SEND_CFG).await?;
-        println!("signature: {}", signature.to_string());
-        Ok(signature)
     }
 
-    pub async fn set_new_buffer_authority(self, settings: LSettings) -> Result<Signature, Error> {
-        let rpc_client = &settings.rpc_client;
-        let buffer_address = self.buffer_account.pubkey();
-        let authority = &settings.keypair;
-        let (recent_blockhash, _) = rpc_client
-            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
-            .await
-            .map_err(|e| Error::RpcError(e))?;
-        let new_authority = if let Some(new_authority) = &self.new_buffer_authority {
-            Pubkey::from_str(new_authority).map_err(|_| Error::UndefinedNewBufferAuthority)?
-        } else {
-            return Err(Error::UndefinedNewBufferAuthority);
-        };
-        let tx = set_new_buffer_auth(&buffer_address, authority, recent_blockhash, &new_authority);
-        let signature = send_tx_and_verify_status(&rpc_client, &tx, SEND_CFG).await?;
-        Ok(signature)
+    // the buffer being resumed if there's a valid one, otherwise the fresh buffer keypair
+    pub fn buffer_pubkey(&self) -> Pubkey {
+        self.resume_buffer
+            .as_deref()
+            .and_then(|address| Pubkey::from_str(address).ok())
+            .unwrap_or(self.buffer_account.pubkey())
     }
 
     // ------> UI COMPONENTS <------ //
@@ -320,7 +419,7 @@ impl LPrograms {
     }
 
     pub fn buffer_address(&self) -> Element<Message> {
-        let buffer_str = self.buffer_account.pubkey().to_string();
+        let buffer_str = self.buffer_pubkey().to_string();
         let label = text(format!("Buffer Address: ",))
             .size(14)
             .color(color!(0x30cbf2));
@@ -330,12 +429,65 @@ impl LPrograms {
             .spacing(10)
             .align_y(iced::Alignment::Center);
 
-        let container = column![label, value_with_copy_btn_row];
+        let resume_label = text("Resume Buffer (address or keypair): ")
+            .size(14)
+            .color(color!(0x30cbf2));
+
+        let resume_value = self.resume_buffer.clone().unwrap_or(String::new());
+
+        let resume_input = text_input("", &resume_value)
+            .size(14)
+            .on_input(Message::ResumeBufferInput);
+
+        let load_buffer_account =
+            button("Load Buffer Keypair").on_press(Message::PickBufferAccount);
+
+        let resume_row = row![resume_input, load_buffer_account]
+            .spacing(10)
+            .align_y(iced::Alignment::Center);
+
+        let mut container = column![label, value_with_copy_btn_row];
+
+        if let (None, Some(seed_phrase)) = (&self.resume_buffer, &self.buffer_seed_phrase) {
+            let seed_phrase_label = text(
+                "To recover this buffer keypair use `solana-keygen recover` with the seed phrase: ",
+            )
+            .size(14)
+            .color(color!(0x30cbf2));
+            let seed_phrase_row = row![
+                text(seed_phrase.clone()).size(14),
+                copy_to_cliboard_btn(seed_phrase)
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center);
+            container = container.push(seed_phrase_label).push(seed_phrase_row);
+        }
+
+        if let Some(keypair_path) = &self.buffer_keypair_path {
+            let keypair_path = keypair_path.display().to_string();
+            let keypair_path_label = text("Buffer keypair saved to: ")
+                .size(14)
+                .color(color!(0x30cbf2));
+            let keypair_path_row = row![
+                text(keypair_path.clone()).size(14),
+                copy_to_cliboard_btn(&keypair_path)
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center);
+            container = container.push(keypair_path_label).push(keypair_path_row);
+        }
+
+        container = container.push(resume_label).push(resume_row);
         container.into()
     }
 
     pub fn write_data_btn(&self) -> Element<Message> {
-        let write_data_btn = button("Write data").on_press(Message::WriteData);
+        let label = if self.resume_buffer.is_some() {
+            "Resume writing data"
+        } else {
+            "Write data"
+        };
+        let write_data_btn = button(label).on_press(Message::WriteData);
         write_data_btn.into()
     }
 
@@ -348,7 +500,42 @@ impl LPrograms {
         let values = text(format!("{}/{}", current_tx_sent, total_to_send)).size(14);
         let progress_bar = progress_bar(0.0..=total_to_send as f32, current_tx_sent as f32);
         let counter = row![label, values];
-        let container = column![counter, progress_bar];
+        let mut container = column![counter, progress_bar];
+
+        if let Some(unit_price) = self.retry_unit_price {
+            container = container.push(
+                text(format!(
+                    "Writes didn't land, retrying with a unit price of {} micro lamports",
+                    unit_price
+                ))
+                .size(14)
+                .color(color!(0xf7c257)),
+            );
+        }
+
+        if self.is_verifying {
+            container = container.push(text("Verifying the buffer contents...").size(14));
+        } else if !self.mismatched_ranges.is_empty() {
+            let ranges: Vec<String> = self
+                .mismatched_ranges
+                .iter()
+                .take(5)
+                .map(|range| format!("{}..{}", range.start, range.end))
+                .collect();
+            let more = match self.mismatched_ranges.len() > ranges.len() {
+                true => format!(" and {} more", self.mismatched_ranges.len() - ranges.len()),
+                false => String::new(),
+            };
+            container = container.push(
+                text(format!(
+                    "Writing mismatched bytes again: {}{}",
+                    ranges.join(", "),
+                    more
+                ))
+                .size(14)
+                .color(color!(0xf7c257)),
+            );
+        }
         container.into()
     }
 
@@ -364,10 +551,12 @@ impl LPrograms {
     }
 
     pub fn set_new_buffer_auth_items(&self) -> Element<Message> {
-        if self.is_data_writed {
-            let new_buffer_auth_input_label = text(format!("New Buffer Authority: ",))
-                .size(14)
-                .color(color!(0x30cbf2));
+        if self.is_data_writed || self.buffer_authority_target.is_some() {
+            let buffer_address = self.buffer_authority_target.unwrap_or(self.buffer_pubkey());
+            let new_buffer_auth_input_label =
+                text(format!("New Buffer Authority for {}: ", buffer_address))
+                    .size(14)
+                    .color(color!(0x30cbf2));
 
             let new_buffer_auth_value = &self
                 .new_buffer_authority
@@ -395,70 +584,536 @@ impl LPrograms {
         }
     }
 
-    pub fn signature_text_with_copy(&self) -> Element<Message> {
-        if let Some(signature) = self.signature {
-            let signature_text = text(format!("tx: {}", signature.to_string()));
-            let copy_btn = copy_to_cliboard_btn(&signature.to_string());
-            let row = row![signature_text, copy_btn]
-                .spacing(5)
-                .align_y(Alignment::Center);
-            row.into()
-        } else {
-            text("").into()
-        }
+    pub fn close_buffer_items(&self) -> Element<Message> {
+        let target_label = text("Buffer to close (current buffer by default): ")
+            .size(14)
+            .color(color!(0x30cbf2));
+
+        let target_value = self.close_buffer_target.clone().unwrap_or(String::new());
+        let target_input = text_input(&self.buffer_pubkey().to_string(), &target_value)
+            .size(14)
+            .on_input(Message::CloseBufferTargetInput);
+
+        let recipient_label = text("Recipient (spill account by default): ")
+            .size(14)
+            .color(color!(0x30cbf2));
+
+        let recipient_value = self.close_recipient.clone().unwrap_or(String::new());
+        let recipient_input = text_input("", &recipient_value)
+            .size(14)
+            .on_input(Message::CloseRecipientInput);
+
+        let lamports_text = match self.close_lamports {
+            Some(lamports) => text(format!(
+                "Recoverable: {:.6} SOL ({} lamports)",
+                lamports as f64 / LAMPORTS_PER_SOL as f64,
+                lamports
+            ))
+            .size(14),
+            None => text("Recoverable: buffer not found").size(14),
+        };
+
+        let close_btn = button("Close Buffer").on_press(Message::CloseBuffer);
+
+        let close_row = row![close_btn, lamports_text]
+            .spacing(10)
+            .align_y(Alignment::Center);
+
+        column![
+            target_label,
+            target_input,
+            recipient_label,
+            recipient_input,
+            close_row
+        ]
+        .spacing(5)
+        .into()
+    }
+
+    // any program id typed in the program input can be checked against the loaded .so
+    pub async fn verify_program(self, settings: LSettings) -> Result<ProgramVerification, Error> {
+        let program_address = self.program_target()?.pubkey();
+        settings
+            .deployer()
+            .verify_program(&program_address, &self.program_bytes)
+            .await
+    }
+
+    pub fn verification_items(&self) -> Element<Message> {
+        let program_address = match self.program_target() {
+            Ok(program) => program.pubkey(),
+            Err(_) => return text("").size(14).into(),
+        };
+
+        let verify_btn = button("Verify On-chain Program")
+            .on_press_maybe((!self.program_bytes.is_empty()).then_some(Message::VerifyProgram));
+
+        let verification_text = match &self.verification {
+            Some(verification) if verification.program_address == program_address.to_string() => {
+                let color = match verification.is_verified() {
+                    true => color!(0x57f78a),
+                    false => color!(0xf75757),
+                };
+                text(verification.to_string()).size(14).color(color)
+            }
+            _ => text(format!("Compare {} with the loaded .so", program_address)).size(14),
+        };
+
+        row![verify_btn, verification_text]
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .into()
     }
-}
 
-pub fn get_program_bytes(program_path: &str) -> Result<Vec<u8>, Error> {
-    match fs::read(program_path) {
-        Ok(bytes) => {
-            println!("bytes len: {}", bytes.len());
-            if bytes.len() == 0 {
-                return Err(Error::InvalidProgramLen);
+    pub fn inspect_pubkey(&self) -> Result<Pubkey, Error> {
+        match self.inspect_input.as_deref() {
+            Some(program_address) if !program_address.is_empty() => {
+                Pubkey::from_str(program_address).map_err(|_| Error::InvalidProgramAccount)
             }
-            Ok(bytes)
+            _ => Ok(self.program_target()?.pubkey()),
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            return Err(Error::UnexpectedError);
+    }
+
+    pub async fn inspect_program(self, settings: LSettings) -> Result<ProgramInfo, Error> {
+        let program_address = self.inspect_pubkey()?;
+        settings.deployer().inspect_program(&program_address).await
+    }
+
+    // the executable is fetched before the dialog opens, a wrong address fails right away
+    pub async fn dump_program(self, settings: LSettings) -> Result<PathBuf, Error> {
+        let address = self.inspect_pubkey()?;
+        let program_bytes = settings.deployer().fetch_executable(&address).await?;
+        let path = save_file(FileType::Program, &format!("{}.so", address)).await?;
+        save_program_bytes(&path, &program_bytes)?;
+        Ok(path)
+    }
+
+    pub fn inspector_items(&self) -> Element<Message> {
+        let inspect_label =
+            text("Inspect or dump a program, or dump a buffer (the loaded program by default): ")
+                .size(14)
+                .color(color!(0x30cbf2));
+
+        let placeholder = self
+            .program_target()
+            .map_or(String::from("Program id"), |program| {
+                program.pubkey().to_string()
+            });
+        let inspect_value = self.inspect_input.clone().unwrap_or(String::new());
+        let inspect_input = text_input(&placeholder, &inspect_value)
+            .size(14)
+            .on_input(Message::InspectProgramInput)
+            .on_submit(Message::InspectProgram);
+
+        let inspect_row = row![
+            inspect_input,
+            button("Inspect").on_press(Message::InspectProgram),
+            button("Dump .so").on_press(Message::DumpProgram)
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        let mut container = column![inspect_label, inspect_row].spacing(5);
+
+        if let Some(dump_path) = &self.dump_path {
+            container = container.push(
+                text(format!("Executable saved to {}", dump_path.display()))
+                    .size(14)
+                    .color(color!(0x57f78a)),
+            );
         }
+
+        let program_info = match &self.program_info {
+            Some(program_info) => program_info,
+            None => return container.into(),
+        };
+
+        let info_row = |label: &str, value: String| {
+            row![
+                text(format!("{}: ", label))
+                    .size(14)
+                    .color(color!(0x30cbf2)),
+                text(value.clone()).size(14),
+                copy_to_cliboard_btn(&value)
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+        };
+        let upgrade_authority = program_info
+            .upgrade_authority
+            .clone()
+            .unwrap_or(String::from("none, the program is immutable"));
+
+        container = container
+            .push(info_row("Program", program_info.program_address.clone()))
+            .push(info_row(
+                "Programdata",
+                program_info.programdata_address.clone(),
+            ))
+            .push(info_row("Upgrade authority", upgrade_authority))
+            .push(info_row(
+                "Last deployed slot",
+                program_info.last_deployed_slot.to_string(),
+            ))
+            .push(info_row(
+                "Data length (bytes)",
+                program_info.data_len.to_string(),
+            ))
+            .push(info_row(
+                "Allocated length (bytes)",
+                program_info.allocated_len.to_string(),
+            ))
+            .push(info_row(
+                "Rent balance (lamports)",
+                program_info.rent_balance.to_string(),
+            ))
+            .push(info_row("SHA-256", program_info.sha256.clone()));
+        container.into()
     }
-}
 
-pub fn get_vec_with_batched_data<T: Clone>(batch_size: usize, base_vec: &Vec<T>) -> Vec<Vec<T>> {
-    let mut offset = 0;
-    let mut vec_with_batchs = Vec::new();
+    pub fn upgrade_authority_items(&self) -> Element<Message> {
+        let program_address = match self.program_target() {
+            Ok(program) => program.pubkey(),
+            Err(_) => return text("").size(14).into(),
+        };
+
+        let new_authority_label = text(format!(
+            "New Upgrade Authority for {} (load its keypair to use the checked transfer): ",
+            program_address
+        ))
+        .size(14)
+        .color(color!(0x30cbf2));
+
+        let new_authority_value = self.new_upgrade_authority.clone().unwrap_or(String::new());
+        let new_authority_input = text_input("", &new_authority_value)
+            .size(14)
+            .on_input(Message::NewUpgradeAuthorityInput);
+
+        let load_new_authority =
+            button("Load New Authority Keypair").on_press(Message::PickNewUpgradeAuthority);
+        let set_authority_label = if self.new_upgrade_authority_keypair.is_some() {
+            "Set Upgrade Authority (checked)"
+        } else {
+            "Set Upgrade Authority"
+        };
+        let set_authority = button(set_authority_label).on_press(Message::SetUpgradeAuthority);
+
+        let immutable_label = text(
+            "Make the program immutable, this can't be undone. Type the program id to confirm: ",
+        )
+        .size(14)
+        .color(color!(0xf75757));
 
-    while offset < base_vec.len() {
-        let chunk_end = (offset + batch_size).min(base_vec.len());
+        let confirmation_value = self.immutable_confirmation.clone().unwrap_or(String::new());
+        let confirmation_input = text_input(&program_address.to_string(), &confirmation_value)
+            .size(14)
+            .on_input(Message::ImmutableConfirmationInput);
 
-        let chunk = base_vec[offset..chunk_end].to_vec();
+        let make_immutable = button("Make Immutable").on_press_maybe(
+            self.is_immutable_confirmed()
+                .then_some(Message::MakeImmutable),
+        );
 
-        offset += batch_size;
-        vec_with_batchs.push(chunk);
+        column![
+            new_authority_label,
+            new_authority_input,
+            row![load_new_authority, set_authority].spacing(10),
+            immutable_label,
+            row![confirmation_input, make_immutable]
+                .spacing(10)
+                .align_y(Alignment::Center)
+        ]
+        .spacing(5)
+        .into()
     }
-    vec_with_batchs
-}
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum Progress {
-    Idle,
-    Sending { sent: usize, total: usize },
-    Completed { buffer_account: Arc<Keypair> },
-}
+    pub fn upgrade_proposal_items(&self) -> Element<Message> {
+        let multisig_label = text("Squads Multisig (derives its default vault): ")
+            .size(14)
+            .color(color!(0x30cbf2));
+        let multisig_value = self.multisig_address.clone().unwrap_or(String::new());
+        let multisig_input = text_input("", &multisig_value)
+            .size(14)
+            .on_input(Message::MultisigAddressInput);
+
+        let vault_label = text(format!(
+            "Vault (upgrade and buffer authority of {}): ",
+            self.buffer_authority_target.unwrap_or(self.buffer_pubkey())
+        ))
+        .size(14)
+        .color(color!(0x30cbf2));
+        let vault_value = self.vault_address.clone().unwrap_or(String::new());
+        let vault_input = text_input("", &vault_value)
+            .size(14)
+            .on_input(Message::VaultAddressInput);
+
+        let spill_label = text("Spill Account (vault by default): ")
+            .size(14)
+            .color(color!(0x30cbf2));
+        let spill_value = self.spill_address.clone().unwrap_or(String::new());
+        let spill_input = text_input("", &spill_value)
+            .size(14)
+            .on_input(Message::SpillAddressInput);
+
+        let export_btn = button("Export Upgrade Proposal").on_press(Message::ExportUpgradeProposal);
+
+        let mut container = column![
+            multisig_label,
+            multisig_input,
+            vault_label,
+            vault_input,
+            spill_label,
+            spill_input,
+            export_btn
+        ]
+        .spacing(5);
+
+        if let Some(upgrade_proposal) = &self.upgrade_proposal {
+            let encoded_row = |label: &str, value: &str| {
+                row![
+                    text(format!("{}: ", label))
+                        .size(14)
+                        .color(color!(0x30cbf2)),
+                    text(value.to_string()).size(14),
+                    copy_to_cliboard_btn(value)
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center)
+            };
+            container = container
+                .push(encoded_row(
+                    "Message (base58)",
+                    &upgrade_proposal.message.base58,
+                ))
+                .push(encoded_row(
+                    "Message (base64)",
+                    &upgrade_proposal.message.base64,
+                ));
+        }
+        container.into()
+    }
+
+    pub fn nonce_items(&self) -> Element<Message> {
+        let nonce_label = text("Durable Nonce Account: ")
+            .size(14)
+            .color(color!(0x30cbf2));
+        let nonce_value = self.nonce_address.clone().unwrap_or(String::new());
+        let nonce_input = text_input("", &nonce_value)
+            .size(14)
+            .on_input(Message::NonceAddressInput);
+
+        let nonce_row = row![
+            nonce_input,
+            button("Create Nonce Account").on_press(Message::CreateNonceAccount),
+            button("Refresh").on_press(Message::RefreshNonce),
+            button("Advance Nonce").on_press(Message::AdvanceNonce)
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        let nonce_info = match &self.nonce_info {
+            Some(nonce_info) => text(format!(
+                "Nonce: {}, authority: {}",
+                nonce_info.blockhash, nonce_info.authority
+            ))
+            .size(14),
+            None => text("Nonce account not found").size(14),
+        };
+
+        let use_nonce = checkbox(
+            "Sign the deploy or upgrade with the durable nonce",
+            self.use_nonce,
+        )
+        .on_toggle(Message::UseNonce)
+        .text_size(14);
+
+        column![nonce_label, nonce_row, nonce_info, use_nonce]
+            .spacing(5)
+            .into()
+    }
+
+    pub fn offline_signer_items(&self) -> Element<Message> {
+        let authority_label =
+            text("Offline Authority (signs the deploy, upgrade or authority change elsewhere): ")
+                .size(14)
+                .color(color!(0x30cbf2));
+        let authority_value = self.offline_authority.clone().unwrap_or(String::new());
+        let authority_input = text_input("", &authority_value)
+            .size(14)
+            .on_input(Message::OfflineAuthorityInput);
+
+        let export_row = row![
+            button("Export Deploy/Upgrade").on_press(Message::ExportOfflineTransaction(
+                OfflineKind::DeployOrUpgrade
+            )),
+            button("Export Set Upgrade Authority").on_press(Message::ExportOfflineTransaction(
+                OfflineKind::SetUpgradeAuthority
+            )),
+            button("Export Make Immutable").on_press_maybe(
+                self.is_immutable_confirmed()
+                    .then_some(Message::ExportOfflineTransaction(
+                        OfflineKind::MakeImmutable
+                    ))
+            )
+        ]
+        .spacing(10);
+
+        let mut container = column![authority_label, authority_input, export_row].spacing(5);
+
+        let offline_transaction = match &self.offline_transaction {
+            Some(offline_transaction) => offline_transaction,
+            None => return container.into(),
+        };
+
+        let message = offline_transaction.message();
+        let encoded_row = |label: &str, value: &str| {
+            row![
+                text(format!("{}: ", label))
+                    .size(14)
+                    .color(color!(0x30cbf2)),
+                text(value.to_string()).size(14),
+                copy_to_cliboard_btn(value)
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+        };
+        let missing_signers = offline_transaction
+            .missing_signers()
+            .iter()
+            .map(Pubkey::to_string)
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let presigner_value = self.presigner_input.clone().unwrap_or(String::new());
+        let presigner_input = text_input("PUBKEY=SIGNATURE", &presigner_value)
+            .size(14)
+            .on_input(Message::PresignerInput)
+            .on_submit(Message::AddPresigner);
+
+        let submit_btn = button("Submit").on_press_maybe(
+            offline_transaction
+                .is_complete()
+                .then_some(Message::SubmitOfflineTransaction),
+        );
+
+        container = container
+            .push(encoded_row("Message (base58)", &message.base58))
+            .push(encoded_row("Message (base64)", &message.base64))
+            .push(text(format!("Missing signatures: {}", missing_signers)).size(14))
+            .push(
+                row![
+                    presigner_input,
+                    button("Add Signature").on_press(Message::AddPresigner),
+                    submit_btn
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            );
+        container.into()
+    }
+
+    pub fn cost_estimate_items(&self) -> Element<Message> {
+        let estimate_btn = button("Estimate Cost").on_press(Message::EstimateCost);
+
+        let cost_estimate = match &self.cost_estimate {
+            Some(cost_estimate) => cost_estimate,
+            None => return estimate_btn.into(),
+        };
+
+        let sol = |lamports: u64| format!("{:.6} SOL", lamports as f64 / LAMPORTS_PER_SOL as f64);
+        let cost_row = |label: &str, value: String| {
+            row![
+                text(format!("{}: ", label))
+                    .size(14)
+                    .color(color!(0x30cbf2)),
+                text(value).size(14)
+            ]
+        };
+
+        let final_tx = if cost_estimate.is_upgrade {
+            "upgrade"
+        } else {
+            "deploy"
+        };
+        let json = serde_json::to_string_pretty(cost_estimate).unwrap_or_default();
+
+        column![
+            row![estimate_btn, copy_to_cliboard_btn(&json)]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            cost_row("Buffer rent", sol(cost_estimate.buffer_rent)),
+            cost_row(
+                "Programdata rent",
+                format!(
+                    "{} ({} bytes reserved, {} of it for the extra room)",
+                    sol(cost_estimate.programdata_rent),
+                    cost_estimate.max_program_len,
+                    sol(cost_estimate.extra_programdata_rent)
+                )
+            ),
+            cost_row("Program account rent", sol(cost_estimate.program_rent)),
+            cost_row(
+                "Programdata extension",
+                match cost_estimate.extension {
+                    Some(extension) => format!(
+                        "{} bytes, {}",
+                        extension.additional_bytes,
+                        sol(extension.rent)
+                    ),
+                    None => String::from("not needed"),
+                }
+            ),
+            cost_row(
+                "Signature fees",
+                format!(
+                    "{} ({} transactions, {} writes + {})",
+                    sol(cost_estimate.signature_fees),
+                    cost_estimate.transactions,
+                    cost_estimate.write_transactions,
+                    final_tx
+                )
+            ),
+            cost_row("Priority fees (at most)", sol(cost_estimate.priority_fees)),
+            cost_row("Total", sol(cost_estimate.total)),
+            cost_row(
+                "Buffer refund",
+                format!(
+                    "{} to {}",
+                    sol(cost_estimate.buffer_refund),
+                    cost_estimate.refund_recipient
+                )
+            ),
+            cost_row("Net total", sol(cost_estimate.net_total)),
+        ]
+        .into()
+    }
+
+    pub fn signature_text_with_copy(&self) -> Element<Message> {
+        if let Some(signature) = self.signature {
+            let signature_text = text(format!("tx: {}", signature.to_string()));
+            let copy_btn = copy_to_cliboard_btn(&signature.to_string());
+            let row = row![signature_text, copy_btn]
+                .spacing(5)
+                .align_y(Alignment::Center);
+            row.into()
+        } else {
+            text("").into()
+        }
+    }
 
-impl Progress {
     pub fn sending_tx_progress_sub(
         programs: LPrograms,
         settings: LSettings,
     ) -> impl Stream<Item = Result<Progress, Error>> {
-        try_channel(1500, move |output| async move {
-            let result = LPrograms::create_buffer_and_write_data(programs, settings, output).await;
-            if let Err(e) = result {
-                return Err(e);
-            } else {
-                Ok(())
-            }
+        try_channel(1500, move |mut output| async move {
+            let buffer = programs.buffer_target();
+            settings
+                .deployer()
+                .write_buffer(buffer, &programs.program_bytes, |progress| {
+                    let _ = output.try_send(progress);
+                })
+                .await?;
+            Ok(())
         })
     }
 
@@ -469,7 +1124,7 @@ impl Progress {
     ) -> iced::Subscription<(I, Result<Progress, Error>)> {
         Subscription::run_with_id(
             id,
-            Progress::sending_tx_progress_sub(programs, settings)
+            LPrograms::sending_tx_progress_sub(programs, settings)
                 .map(move |progress| (id, progress)),
         )
     }
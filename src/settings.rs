@@ -1,20 +1,50 @@
-use std::{
-    path::{Path, PathBuf},
-    sync::Arc,
-};
+use std::{path::PathBuf, str::FromStr, sync::Arc};
 
 use iced::{
     color,
-    widget::{button, column, container, row, text, text_input, Space},
+    widget::{button, checkbox, column, container, pick_list, row, text, text_input, Space},
     Alignment, Element, Length,
 };
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{native_token::LAMPORTS_PER_SOL, signature::Keypair, signer::Signer};
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+};
 
-use crate::{components::copy_to_cliboard_btn, errors::Error, programs::LPrograms};
-use crate::{files::default_keypair_path, keypair::load_keypair_from_file, Message};
+use crate::{components::copy_to_cliboard_btn, programs::LPrograms, Message};
+use lich_deployer::{
+    app_settings::AppSettings,
+    clusters::{is_mainnet_url, load_cluster_profiles, ClusterProfile, ClusterProfiles, RPC_URL},
+    deployer::{Deployer, MaxProgramLen},
+    errors::Error,
+    fees::{UnitLimitMode, UnitPriceMode},
+    files::default_keypair_path,
+    instructions::{PriorityFees, DEFAULT_UNIT_LIMIT, DEFAULT_UNIT_PRICE},
+    keypair::load_keypair_from_file,
+    solana_config::{default_config_path, load_solana_config, SolanaConfig},
+};
 
-const RPC_URL: &str = "https://api.devnet.solana.com";
+pub const UNIT_PRICE_MODES: [UnitPriceMode; 5] = [
+    UnitPriceMode::Fixed,
+    UnitPriceMode::Auto { percentile: 50 },
+    UnitPriceMode::Auto { percentile: 75 },
+    UnitPriceMode::Auto { percentile: 90 },
+    UnitPriceMode::Auto { percentile: 95 },
+];
+pub const UNIT_LIMIT_MODES: [UnitLimitMode; 4] = [
+    UnitLimitMode::Fixed,
+    UnitLimitMode::Simulated { margin_percent: 10 },
+    UnitLimitMode::Simulated { margin_percent: 20 },
+    UnitLimitMode::Simulated { margin_percent: 50 },
+];
+pub const COMMITMENT_LEVELS: [CommitmentLevel; 3] = [
+    CommitmentLevel::Processed,
+    CommitmentLevel::Confirmed,
+    CommitmentLevel::Finalized,
+];
 
 #[derive(Clone)]
 pub struct LSettings {
@@ -23,42 +53,228 @@ pub struct LSettings {
     pub program_path: Option<PathBuf>,
     pub keypair: Arc<Keypair>,
     pub balance: Option<u64>,
+    // None when the authority pays its own fees
+    pub fee_payer_path: Option<PathBuf>,
+    pub fee_payer: Option<Arc<Keypair>>,
+    pub fee_payer_balance: Option<u64>,
+    // spill account of the upgrades and default recipient of the closes, kept as typed
+    pub refund_recipient_input: String,
     pub unit_price: u64,
     pub unit_limit: u32,
+    pub unit_price_mode: UnitPriceMode,
+    pub unit_limit_mode: UnitLimitMode,
+    // parsed with MaxProgramLen::from_str, kept as typed so the input can be edited freely
+    pub max_program_len_input: String,
+    pub auto_extend: bool,
+    pub websocket_url: String,
+    pub commitment: CommitmentConfig,
+    pub config_path: Option<PathBuf>,
+    pub cluster_profiles: ClusterProfiles,
+    // the rpc client is only replaced when the url is submitted, not on every keystroke
+    pub rpc_url_input: String,
+    // guessed from the url until the genesis hash of the rpc comes back
+    pub is_mainnet: bool,
+    pub profile_name_input: String,
 }
 
 impl Default for LSettings {
     fn default() -> Self {
         let default_keypair_path = default_keypair_path();
-        Self {
+        let mut settings = Self {
             rpc_client: Arc::new(RpcClient::new(RPC_URL.to_string())),
             keypair_path: Some(default_keypair_path.to_path_buf()),
             program_path: None,
-            keypair: load_keypair_from_file(default_keypair_path).into(),
+            // a placeholder until a keypair is loaded, the balance fetch reports the missing file
+            keypair: load_keypair_from_file(default_keypair_path)
+                .unwrap_or(Keypair::new())
+                .into(),
             balance: None,
-            unit_limit: 25000,
-            unit_price: 550_000,
+            fee_payer_path: None,
+            fee_payer: None,
+            fee_payer_balance: None,
+            refund_recipient_input: String::new(),
+            unit_limit: DEFAULT_UNIT_LIMIT,
+            unit_price: DEFAULT_UNIT_PRICE,
+            unit_price_mode: UnitPriceMode::Fixed,
+            unit_limit_mode: UnitLimitMode::default(),
+            max_program_len_input: MaxProgramLen::Exact.to_string(),
+            auto_extend: false,
+            websocket_url: String::new(),
+            commitment: CommitmentConfig::confirmed(),
+            config_path: default_config_path(),
+            cluster_profiles: load_cluster_profiles(),
+            rpc_url_input: RPC_URL.to_string(),
+            is_mainnet: is_mainnet_url(RPC_URL),
+            profile_name_input: String::new(),
+        };
+
+        // the solana cli config, when there's one, seeds the rpc, keypair and commitment
+        if let Some(solana_config) = settings
+            .config_path
+            .as_deref()
+            .and_then(|path| load_solana_config(path).ok())
+        {
+            settings.apply_solana_config(solana_config);
         }
+
+        // the profile picked in the last session wins over the cli config
+        if let Some(profile) = settings.cluster_profiles.active_profile().cloned() {
+            settings.apply_cluster_profile(&profile);
+        }
+        settings
     }
 }
 
 impl LSettings {
-    pub fn view(&self, program_module: &LPrograms) -> Element<'static, Message> {
-        let keypair_path = self
-            .keypair_path
-            .as_deref()
-            .map(Path::to_path_buf)
-            .unwrap_or_else(|| PathBuf::from(""));
+    pub fn set_rpc_url(&mut self, rpc_url: String) {
+        self.rpc_url_input = rpc_url.clone();
+        self.is_mainnet = is_mainnet_url(&rpc_url);
+        self.rpc_client = Arc::new(RpcClient::new_with_commitment(rpc_url, self.commitment));
+    }
 
-        let load_keypair = button("Load Keypair").on_press(Message::PickProgramAuthority);
+    // an unreadable keypair keeps the loaded one, the rest is applied anyway
+    pub fn apply_solana_config(&mut self, solana_config: SolanaConfig) -> Result<(), Error> {
+        self.commitment = solana_config.commitment;
+        self.set_rpc_url(solana_config.rpc_url);
+        self.websocket_url = solana_config.websocket_url;
+        self.config_path = Some(solana_config.path);
+        self.set_keypair(solana_config.keypair_path)
+    }
+
+    pub fn set_keypair(&mut self, keypair_path: PathBuf) -> Result<(), Error> {
+        self.keypair = load_keypair_from_file(keypair_path.to_path_buf())?.into();
+        self.keypair_path = Some(keypair_path);
+        Ok(())
+    }
+
+    // the program and its keypair are loaded by the caller, unreadable keypairs are reported
+    // after everything else is applied
+    pub fn apply_app_settings(&mut self, app_settings: &AppSettings) -> Result<(), Error> {
+        self.commitment = CommitmentConfig {
+            commitment: app_settings.commitment,
+        };
+        self.set_rpc_url(app_settings.rpc_url.clone());
+        self.websocket_url = app_settings.websocket_url.clone();
+        self.unit_limit = app_settings.unit_limit;
+        self.unit_price = app_settings.unit_price;
+        self.unit_price_mode = app_settings.unit_price_mode;
+        self.unit_limit_mode = app_settings.unit_limit_mode;
+        self.max_program_len_input = app_settings.max_program_len.to_string();
+        self.auto_extend = app_settings.auto_extend;
+        self.refund_recipient_input = app_settings.refund_recipient.clone();
+        if app_settings.solana_config_path.is_some() {
+            self.config_path = app_settings.solana_config_path.clone();
+        }
+        let keypair = match &app_settings.keypair_path {
+            Some(keypair_path) => self.set_keypair(keypair_path.to_path_buf()),
+            None => Ok(()),
+        };
+        let fee_payer = match &app_settings.fee_payer_path {
+            Some(fee_payer_path) => self.set_fee_payer(fee_payer_path.to_path_buf()),
+            None => Ok(()),
+        };
+        keypair.and(fee_payer)
+    }
+
+    // the program keypair lives in LPrograms, it's loaded by the caller
+    pub fn apply_cluster_profile(&mut self, profile: &ClusterProfile) -> Result<(), Error> {
+        self.set_rpc_url(profile.rpc_url.clone());
+        self.websocket_url = profile.websocket_url.clone();
+        self.unit_limit = profile.unit_limit;
+        self.unit_price = profile.unit_price;
+        self.unit_price_mode = profile.unit_price_mode;
+        self.unit_limit_mode = profile.unit_limit_mode;
+        self.profile_name_input = profile.name.clone();
+        self.cluster_profiles.active = Some(profile.name.clone());
+        match &profile.keypair_path {
+            Some(keypair_path) => self.set_keypair(keypair_path.to_path_buf()),
+            None => Ok(()),
+        }
+    }
 
-        let keypair = load_keypair_from_file(keypair_path);
+    // snapshot of the current settings under the name typed in the profile input
+    pub fn current_profile(&self, program_keypair_path: Option<PathBuf>) -> ClusterProfile {
+        ClusterProfile {
+            name: self.profile_name_input.trim().to_string(),
+            rpc_url: self.rpc_client.url(),
+            websocket_url: self.websocket_url.clone(),
+            unit_limit: self.unit_limit,
+            unit_price: self.unit_price,
+            unit_price_mode: self.unit_price_mode,
+            unit_limit_mode: self.unit_limit_mode,
+            keypair_path: self.keypair_path.clone(),
+            program_keypair_path,
+        }
+    }
+
+    pub fn solana_config(&self) -> Option<SolanaConfig> {
+        Some(SolanaConfig {
+            path: self.config_path.clone()?,
+            rpc_url: self.rpc_client.url(),
+            websocket_url: self.websocket_url.clone(),
+            keypair_path: self.keypair_path.clone().unwrap_or(default_keypair_path()),
+            commitment: self.commitment,
+        })
+    }
+
+    pub fn set_fee_payer(&mut self, fee_payer_path: PathBuf) -> Result<(), Error> {
+        self.fee_payer = Some(load_keypair_from_file(fee_payer_path.to_path_buf())?.into());
+        self.fee_payer_path = Some(fee_payer_path);
+        self.fee_payer_balance = None;
+        Ok(())
+    }
+
+    // the authority goes back to paying the fees
+    pub fn clear_fee_payer(&mut self) {
+        self.fee_payer = None;
+        self.fee_payer_path = None;
+        self.fee_payer_balance = None;
+    }
+
+    pub fn fee_payer(&self) -> Arc<Keypair> {
+        self.fee_payer.clone().unwrap_or(self.keypair.clone())
+    }
+
+    // None when it's left empty and the fee payer gets the refunds
+    pub fn refund_recipient(&self) -> Result<Option<Pubkey>, Error> {
+        match self.refund_recipient_input.trim() {
+            "" => Ok(None),
+            refund_recipient => Pubkey::from_str(refund_recipient)
+                .map(Some)
+                .map_err(|_| Error::InvalidSpillAddress),
+        }
+    }
+
+    // an invalid input falls back to sizing the deploy exactly to the binary
+    pub fn max_program_len(&self) -> MaxProgramLen {
+        self.max_program_len_input.parse().unwrap_or_default()
+    }
+
+    pub fn deployer(&self) -> Deployer {
+        Deployer::new(
+            self.rpc_client.clone(),
+            self.keypair.clone(),
+            PriorityFees {
+                unit_limit: self.unit_limit,
+                unit_price: self.unit_price,
+            },
+        )
+        .with_unit_price_mode(self.unit_price_mode)
+        .with_unit_limit_mode(self.unit_limit_mode)
+        .with_max_program_len(self.max_program_len())
+        .with_auto_extend(self.auto_extend)
+        .with_fee_payer(self.fee_payer())
+        .with_spill_address(self.refund_recipient().ok().flatten())
+    }
+
+    pub fn view(&self, program_module: &LPrograms) -> Element<'static, Message> {
+        let load_keypair = button("Load Keypair").on_press(Message::PickProgramAuthority);
 
         let label = text(format!("Wallet address: ",))
             .size(14)
             .color(color!(0x30cbf2));
 
-        let wallet_address = keypair.pubkey().to_string();
+        let wallet_address = self.keypair.pubkey().to_string();
         let copy_btn = copy_to_cliboard_btn(&wallet_address);
 
         let value = text(wallet_address).size(14);
@@ -83,21 +299,143 @@ impl LSettings {
         ]
         .align_y(Alignment::Center);
 
-        let rpc_label = text(format!("RPC Client URL: ",))
+        let fee_payer_label = text("Fee payer: ").size(14).color(color!(0x30cbf2));
+
+        let fee_payer_row = match &self.fee_payer {
+            Some(fee_payer) => {
+                let fee_payer_address = fee_payer.pubkey().to_string();
+                let fee_payer_balance = match self.fee_payer_balance {
+                    Some(balance) => format!("{:.3} SOL", balance as f32 / LAMPORTS_PER_SOL as f32),
+                    None => String::from("Loading balance..."),
+                };
+                row![
+                    fee_payer_label,
+                    text(fee_payer_address.clone()).size(14),
+                    copy_to_cliboard_btn(&fee_payer_address),
+                    text(fee_payer_balance).size(14),
+                    button("Load Fee Payer").on_press(Message::PickFeePayer),
+                    button("Authority Pays").on_press(Message::ClearFeePayer)
+                ]
+            }
+            None => row![
+                fee_payer_label,
+                text("the authority pays the fees").size(14),
+                button("Load Fee Payer").on_press(Message::PickFeePayer)
+            ],
+        }
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        let refund_recipient_label =
+            text("Spill / refund recipient (press enter to update the estimate): ")
+                .size(14)
+                .color(color!(0x30cbf2));
+
+        let refund_recipient_input = text_input("Fee payer", &self.refund_recipient_input)
+            .size(14)
+            .on_input(Message::RefundRecipientInput)
+            .on_submit(Message::EstimateCost);
+
+        let refund_recipient_text = match self.refund_recipient() {
+            Ok(Some(_)) => text("").size(14),
+            Ok(None) => text("Refunds go to the fee payer").size(14),
+            Err(_) => text("Invalid, the fee payer gets the refunds")
+                .size(14)
+                .color(color!(0xf75757)),
+        };
+
+        let refund_recipient_column = column![
+            refund_recipient_label,
+            row![refund_recipient_input, refund_recipient_text]
+                .spacing(10)
+                .align_y(Alignment::Center)
+        ];
+
+        let profile_label = text("Cluster profile: ").size(14).color(color!(0x30cbf2));
+
+        let profile_picker = pick_list(
+            self.cluster_profiles.profiles.clone(),
+            self.cluster_profiles.active_profile().cloned(),
+            Message::SelectClusterProfile,
+        )
+        .text_size(14);
+
+        let profile_name_input = text_input("Profile name", &self.profile_name_input)
+            .size(14)
+            .on_input(Message::ProfileNameInput)
+            .on_submit(Message::SaveClusterProfile);
+
+        let save_profile = button("Save Profile").on_press(Message::SaveClusterProfile);
+
+        let profile_row = row![
+            profile_label,
+            profile_picker,
+            profile_name_input,
+            save_profile
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        let rpc_label = text("RPC Client URL (press enter to apply): ")
             .size(14)
             .color(color!(0x30cbf2));
 
-        let rpc_input = text_input("", &self.rpc_client.url())
+        let rpc_input = text_input("", &self.rpc_url_input)
+            .size(14)
+            .on_input(Message::RpcUrlInput)
+            .on_submit(Message::RpcClient(self.rpc_url_input.clone()));
+
+        let mut set_rpc_client = column![rpc_label, rpc_input];
+
+        if self.is_mainnet {
+            let mainnet_warning = text("MAINNET-BETA IS ACTIVE: every transaction spends real SOL")
+                .size(18)
+                .color(color!(0xf75757));
+            set_rpc_client = set_rpc_client.push(mainnet_warning);
+        }
+
+        let websocket_label = text("Websocket URL: ").size(14).color(color!(0x30cbf2));
+
+        let websocket_input = text_input("", &self.websocket_url)
             .size(14)
-            .on_input(Message::RpcClient);
+            .on_input(Message::WebsocketUrl);
+
+        let commitment_label = text("Commitment: ").size(14).color(color!(0x30cbf2));
 
-        let set_rpc_client = column![rpc_label, rpc_input];
+        let commitment_picker = pick_list(
+            COMMITMENT_LEVELS,
+            Some(self.commitment.commitment),
+            Message::Commitment,
+        )
+        .text_size(14);
+
+        let websocket_and_commitment = row![
+            column![websocket_label, websocket_input],
+            column![commitment_label, commitment_picker]
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        let config_path = self
+            .config_path
+            .as_deref()
+            .map_or(String::from("No solana cli config loaded"), |path| {
+                path.display().to_string()
+            });
+
+        let config_row = row![
+            text(format!("CLI config: {}", config_path)).size(14),
+            button("Load Config").on_press(Message::PickSolanaConfig),
+            button("Save to Config").on_press(Message::SaveSolanaConfig),
+            button("Reset to Defaults").on_press(Message::ResetSettings)
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
 
         let load_program = button("Load Program .so").on_press(Message::PickProgram);
 
         let program_address = program_module
-            .program_account
-            .as_ref()
+            .program_target()
             .map_or(String::from(""), |v| v.pubkey().to_string());
 
         let program_label = text(format!("Program pubkey: ",))
@@ -119,16 +457,53 @@ impl LSettings {
         let load_program_account =
             button("Load Program Account").on_press(Message::PickProgramAccount);
 
+        let program_address_value = program_module
+            .program_address_input
+            .clone()
+            .unwrap_or(String::new());
+        let program_address_input =
+            text_input("Or a program id to upgrade", &program_address_value)
+                .size(14)
+                .on_input(Message::ProgramAddressInput);
+
         let program_account_column = column![
             program_label,
             program_address_with_copy_btn_row,
-            load_program_account
+            load_program_account,
+            program_address_input
         ]
         .spacing(5);
 
+        let max_program_len_input = text_input("exact, bytes or 2x", &self.max_program_len_input)
+            .size(14)
+            .on_input(Message::MaxProgramLenInput);
+
+        let max_program_len_text = match self
+            .max_program_len_input
+            .parse::<MaxProgramLen>()
+            .and_then(|max_program_len| max_program_len.resolve(program_module.program_bytes.len()))
+        {
+            Ok(max_program_len) => text(format!("{} bytes", max_program_len)).size(14),
+            Err(_) => text("Invalid, the exact size is used")
+                .size(14)
+                .color(color!(0xf75757)),
+        };
+
         let program_binaries_column = column![
             text("Program size: ").color(color!(0x30cbf2)).size(14),
             text(format!("{} bytes", program_module.program_bytes.len())).size(14),
+            text("Max program length on deploy: ")
+                .color(color!(0x30cbf2))
+                .size(14),
+            row![max_program_len_input, max_program_len_text]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            checkbox(
+                "Extend programdata when an upgrade doesn't fit",
+                self.auto_extend
+            )
+            .on_toggle(Message::AutoExtend)
+            .text_size(14),
             load_program
         ]
         .spacing(5);
@@ -141,24 +516,52 @@ impl LSettings {
         .spacing(10)
         .align_y(Alignment::Center);
 
-        let comput_unit_limit_label = text(format!("Comput Unit Limit: ",))
-            .size(14)
-            .color(color!(0x30cbf2));
+        // when simulating, the limit typed here is only used if a simulation fails
+        let comput_unit_limit_label = match self.unit_limit_mode {
+            UnitLimitMode::Fixed => text("Comput Unit Limit: "),
+            UnitLimitMode::Simulated { .. } => text("Fallback Comput Unit Limit: "),
+        }
+        .size(14)
+        .color(color!(0x30cbf2));
+
+        let unit_limit_mode_picker = pick_list(
+            UNIT_LIMIT_MODES,
+            Some(self.unit_limit_mode),
+            Message::UnitLimitMode,
+        )
+        .text_size(14);
 
         let comput_unit_limit_input = text_input("", &self.unit_limit.to_string())
             .size(14)
             .on_input(Message::ComputeUniteLimit);
 
-        let comput_unit_price_label = text(format!("Comput Unit Price: ",))
-            .size(14)
-            .color(color!(0x30cbf2));
+        // in auto mode the price typed here is the most the app will pay
+        let comput_unit_price_label = match self.unit_price_mode {
+            UnitPriceMode::Fixed => text("Comput Unit Price: "),
+            UnitPriceMode::Auto { .. } => text("Max Comput Unit Price: "),
+        }
+        .size(14)
+        .color(color!(0x30cbf2));
+
+        let unit_price_mode_picker = pick_list(
+            UNIT_PRICE_MODES,
+            Some(self.unit_price_mode),
+            Message::UnitPriceMode,
+        )
+        .text_size(14);
 
         let comput_unit_price_input = text_input("", &self.unit_price.to_string())
             .size(14)
             .on_input(Message::ComputeUnitPrice);
 
-        let comput_limit_column = column![comput_unit_limit_label, comput_unit_limit_input];
-        let comput_price_column = column![comput_unit_price_label, comput_unit_price_input];
+        let comput_limit_column = column![
+            comput_unit_limit_label,
+            row![comput_unit_limit_input, unit_limit_mode_picker].spacing(10)
+        ];
+        let comput_price_column = column![
+            comput_unit_price_label,
+            row![comput_unit_price_input, unit_price_mode_picker].spacing(10)
+        ];
 
         let comput_unit_items = row![
             comput_limit_column,
@@ -170,7 +573,12 @@ impl LSettings {
         container(
             column![
                 column_wallet_balance,
+                fee_payer_row,
+                refund_recipient_column,
+                profile_row,
+                config_row,
                 set_rpc_client,
+                websocket_and_commitment,
                 comput_unit_items,
                 program_stuff_row
             ]
@@ -182,7 +590,7 @@ impl LSettings {
 }
 
 pub async fn keypair_balance(path: PathBuf, rpc_client: Arc<RpcClient>) -> Result<u64, Error> {
-    let keypair = load_keypair_from_file(path);
+    let keypair = load_keypair_from_file(path)?;
     rpc_client
         .get_balance(&keypair.pubkey())
         .await
@@ -0,0 +1,63 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use solana_cli_config::{Config, CONFIG_FILE};
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use crate::errors::Error;
+
+// the values the app takes from the solana cli config file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolanaConfig {
+    pub path: PathBuf,
+    pub rpc_url: String,
+    pub websocket_url: String,
+    pub keypair_path: PathBuf,
+    pub commitment: CommitmentConfig,
+}
+
+// ~/.config/solana/cli/config.yml, same location the solana cli uses
+pub fn default_config_path() -> Option<PathBuf> {
+    CONFIG_FILE.as_ref().map(PathBuf::from)
+}
+
+pub fn load_solana_config(path: &Path) -> Result<SolanaConfig, Error> {
+    let config_file = path.to_str().ok_or(Error::InvalidConfigFile)?;
+    let config = Config::load(config_file).map_err(|_| Error::InvalidConfigFile)?;
+
+    // an empty websocket url means the cli computes it from the rpc url
+    let websocket_url = if config.websocket_url.is_empty() {
+        Config::compute_websocket_url(&config.json_rpc_url)
+    } else {
+        config.websocket_url
+    };
+
+    Ok(SolanaConfig {
+        path: path.to_path_buf(),
+        commitment: CommitmentConfig::from_str(&config.commitment)
+            .unwrap_or(CommitmentConfig::confirmed()),
+        rpc_url: config.json_rpc_url,
+        websocket_url,
+        keypair_path: PathBuf::from(config.keypair_path),
+    })
+}
+
+// writes the values back, anything else in the file (like address labels) is kept
+pub fn save_solana_config(solana_config: &SolanaConfig) -> Result<(), Error> {
+    let config_file = solana_config
+        .path
+        .to_str()
+        .ok_or(Error::InvalidConfigFile)?;
+    let mut config = Config::load(config_file).unwrap_or_default();
+
+    config.json_rpc_url = solana_config.rpc_url.clone();
+    config.websocket_url = solana_config.websocket_url.clone();
+    config.keypair_path = solana_config.keypair_path.to_string_lossy().to_string();
+    config.commitment = solana_config.commitment.commitment.to_string();
+
+    config
+        .save(config_file)
+        .map_err(|_| Error::ConfigWriteError)
+}
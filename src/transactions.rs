@@ -1,12 +1,44 @@
 use std::time::Duration;
 
+use base64::{engine::general_purpose::STANDARD, Engine};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_config::RpcSendTransactionConfig;
-use solana_sdk::{signature::Signature, transaction::Transaction};
+use solana_sdk::{message::Message, signature::Signature, transaction::Transaction};
 use tokio::time;
 
 use crate::errors::Error;
 
+// an unsigned message in the encodings wallets and multisig UIs accept when pasted
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerializedMessage {
+    pub base58: String,
+    pub base64: String,
+}
+
+impl From<&Message> for SerializedMessage {
+    fn from(message: &Message) -> Self {
+        let bytes = message.serialize();
+        Self {
+            base58: bs58::encode(&bytes).into_string(),
+            base64: STANDARD.encode(&bytes),
+        }
+    }
+}
+
+// accepts either encoding, a message pasted back from a wallet or a multisig UI
+pub fn decode_message(encoded: &str) -> Result<Message, Error> {
+    let encoded = encoded.trim();
+    // a base58 string can also be valid base64, so each decoding has to deserialize
+    [
+        STANDARD.decode(encoded).ok(),
+        bs58::decode(encoded).into_vec().ok(),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|bytes| bincode::deserialize::<Message>(&bytes).ok())
+    .ok_or(Error::InvalidMessage)
+}
+
 // this send the tx and verify its confimation
 // if there's any error on the tx status, the loop will break.
 pub async fn send_tx_and_verify_status(
@@ -42,3 +74,45 @@ pub async fn send_tx_and_verify_status(
     }
     Ok(signature)
 }
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{hash::Hash, pubkey::Pubkey, system_instruction::transfer};
+
+    use super::*;
+
+    fn message() -> Message {
+        let payer = Pubkey::new_unique();
+        Message::new_with_blockhash(
+            &[transfer(&payer, &Pubkey::new_unique(), 1)],
+            Some(&payer),
+            &Hash::new_unique(),
+        )
+    }
+
+    #[test]
+    fn decode_message_accepts_both_encodings() {
+        let message = message();
+        let serialized = SerializedMessage::from(&message);
+        assert_eq!(
+            decode_message(&serialized.base58).ok(),
+            Some(message.clone())
+        );
+        assert_eq!(
+            decode_message(&serialized.base64).ok(),
+            Some(message.clone())
+        );
+        // pasted with a trailing newline
+        assert_eq!(
+            decode_message(&format!("{}\n", serialized.base64)).ok(),
+            Some(message)
+        );
+    }
+
+    #[test]
+    fn decode_message_rejects_anything_else() {
+        assert!(decode_message("").is_err());
+        assert!(decode_message("not a message").is_err());
+        assert!(decode_message(&STANDARD.encode(b"valid base64, not a message")).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use std::{fmt, ops::Range};
+
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{bpf_loader_upgradeable::UpgradeableLoaderState, hash::hash, pubkey::Pubkey};
+
+use crate::{deployer::mismatched_ranges, errors::Error, inspect::fetch_programdata};
+
+// what's deployed on chain compared with a local binary
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgramVerification {
+    pub program_address: String,
+    // sha-256 in hex, the same `sha256sum` prints for the .so
+    pub local_hash: String,
+    // over the on chain bytes cut to the elf or local length, unless more than padding follows
+    pub onchain_hash: String,
+    pub local_len: usize,
+    pub onchain_len: usize,
+    pub mismatched_ranges: Vec<Range<usize>>,
+}
+
+impl ProgramVerification {
+    pub fn is_verified(&self) -> bool {
+        self.local_hash == self.onchain_hash
+    }
+}
+
+impl fmt::Display for ProgramVerification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_verified() {
+            return write!(f, "verified, sha-256 {}", self.onchain_hash);
+        }
+        write!(
+            f,
+            "mismatch: local {} bytes (sha-256 {}), on chain {} bytes (sha-256 {})",
+            self.local_len, self.local_hash, self.onchain_len, self.onchain_hash
+        )?;
+        if let Some(first_range) = self.mismatched_ranges.first() {
+            write!(
+                f,
+                ", {} ranges differ starting at byte {}",
+                self.mismatched_ranges.len(),
+                first_range.start
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hash(bytes)
+        .to_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// the programdata account is allocated for the max program length, the bytes above the
+// binary stay zeroed
+pub fn trim_zero_padding(bytes: &[u8]) -> &[u8] {
+    let len = bytes
+        .iter()
+        .rposition(|byte| *byte != 0)
+        .map_or(0, |last| last + 1);
+    &bytes[..len]
+}
+
+// the executable stored in the programdata account of an upgradeable program, without the
+// loader header but zero padded up to the max program length
+pub async fn fetch_program_executable(
+    rpc_client: &RpcClient,
+    program_address: &Pubkey,
+) -> Result<Vec<u8>, Error> {
+    let (_, programdata) = fetch_programdata(rpc_client, program_address).await?;
+
+    let header_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    match programdata.data.get(header_len..) {
+        Some(executable) => Ok(executable.to_vec()),
+        None => Err(Error::InvalidProgramAccount),
+    }
+}
+
+fn le_bytes<const N: usize>(bytes: &[u8], offset: usize) -> Option<[u8; N]> {
+    bytes.get(offset..offset + N)?.try_into().ok()
+}
+
+// where the section header table of a 64 bit little endian elf ends, the linker writes it
+// last so it's the length of the file
+pub fn elf_len(bytes: &[u8]) -> Option<usize> {
+    if !bytes.starts_with(b"\x7fELF") || bytes.get(4..6) != Some(&[2, 1]) {
+        return None;
+    }
+    let section_headers_offset = u64::from_le_bytes(le_bytes(bytes, 0x28)?) as usize;
+    let section_header_len = u16::from_le_bytes(le_bytes(bytes, 0x3a)?) as usize;
+    let section_headers = u16::from_le_bytes(le_bytes(bytes, 0x3c)?) as usize;
+    section_headers_offset.checked_add(section_header_len * section_headers)
+}
+
+// the executable in a zero padded programdata region, an elf usually ends in zero bytes that
+// belong to it (the last section header), so it's cut where the elf says it ends
+pub fn deployed_executable(allocated: &[u8]) -> &[u8] {
+    let trimmed_len = trim_zero_padding(allocated).len();
+    let len = elf_len(allocated)
+        .map_or(trimmed_len, |elf_len| elf_len.max(trimmed_len))
+        .min(allocated.len());
+    &allocated[..len]
+}
+
+// the same, but never shorter than the local binary it's compared with
+pub fn onchain_executable(allocated: &[u8], local_len: usize) -> &[u8] {
+    let len = deployed_executable(allocated)
+        .len()
+        .max(local_len)
+        .min(allocated.len());
+    &allocated[..len]
+}
+
+// the local binary is compared as is, its hash is the one `sha256sum` prints
+pub async fn verify_program(
+    rpc_client: &RpcClient,
+    program_address: &Pubkey,
+    program_bytes: &[u8],
+) -> Result<ProgramVerification, Error> {
+    if program_bytes.is_empty() {
+        return Err(Error::InvalidProgramLen);
+    }
+    let allocated = fetch_program_executable(rpc_client, program_address).await?;
+    let onchain_bytes = onchain_executable(&allocated, program_bytes.len());
+
+    // bytes only present on chain are reported too
+    let mut mismatched_ranges = mismatched_ranges(onchain_bytes, program_bytes);
+    if onchain_bytes.len() > program_bytes.len() {
+        match mismatched_ranges.last_mut() {
+            Some(range) if range.end == program_bytes.len() => range.end = onchain_bytes.len(),
+            _ => mismatched_ranges.push(program_bytes.len()..onchain_bytes.len()),
+        }
+    }
+
+    Ok(ProgramVerification {
+        program_address: program_address.to_string(),
+        local_hash: sha256_hex(program_bytes),
+        onchain_hash: sha256_hex(onchain_bytes),
+        local_len: program_bytes.len(),
+        onchain_len: onchain_bytes.len(),
+        mismatched_ranges,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn onchain_executable_keeps_the_zeros_the_binary_ends_with() {
+        let mut allocated = vec![7; 100];
+        allocated.extend([0; 8]);
+        allocated.extend([0; 900]);
+        // the local binary is the 100 bytes plus its own 8 trailing zeros
+        assert_eq!(onchain_executable(&allocated, 108), &allocated[..108]);
+    }
+
+    #[test]
+    fn onchain_executable_keeps_more_than_padding() {
+        let mut allocated = vec![7; 200];
+        allocated.extend([0; 800]);
+        assert_eq!(onchain_executable(&allocated, 108), &allocated[..200]);
+    }
+
+    // an elf whose section header table, the end of the file, ends in zeros
+    fn elf_ending_in_zeros() -> Vec<u8> {
+        let mut elf = vec![7; 200];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[4..6].copy_from_slice(&[2, 1]);
+        elf[0x28..0x30].copy_from_slice(&72u64.to_le_bytes());
+        elf[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+        elf[0x3c..0x3e].copy_from_slice(&2u16.to_le_bytes());
+        elf.truncate(72);
+        elf.extend([7; 112]);
+        elf.extend([0; 16]);
+        elf
+    }
+
+    #[test]
+    fn elf_len_reads_the_section_header_table_end() {
+        let elf = elf_ending_in_zeros();
+        assert_eq!(elf_len(&elf), Some(elf.len()));
+        assert_eq!(elf_len(&[7; 100]), None);
+    }
+
+    #[test]
+    fn inspect_and_verify_hash_the_same_bytes() {
+        let elf = elf_ending_in_zeros();
+        let mut allocated = elf.clone();
+        allocated.extend([0; 1000]);
+
+        // what the inspector hashes without a local binary
+        let inspected = deployed_executable(&allocated);
+        // what verification hashes against the local binary
+        let verified = onchain_executable(&allocated, elf.len());
+        assert_eq!(sha256_hex(inspected), sha256_hex(&elf));
+        assert_eq!(sha256_hex(verified), sha256_hex(&elf));
+        assert_ne!(trim_zero_padding(&allocated).len(), elf.len());
+    }
+
+    #[test]
+    fn onchain_executable_is_never_longer_than_the_account() {
+        let allocated = vec![7; 100];
+        assert_eq!(onchain_executable(&allocated, 500), allocated.as_slice());
+    }
+}